@@ -16,7 +16,7 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use crate::AsyncSerialize;
 
-use super::FsError;
+use super::{from_ipld, FsError};
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
@@ -49,7 +49,7 @@ pub trait BlockStore {
     async fn get_deserializable<'a, D: DeserializeOwned>(&'a self, cid: &Cid) -> Result<D> {
         let bytes = self.get_block(cid).await?;
         let ipld = Ipld::decode(DagCborCodec, &mut Cursor::new(bytes.as_ref()))?;
-        Ok(ipld_serde::from_ipld::<D>(ipld)?)
+        from_ipld(ipld)
     }
 }
 
@@ -1 +1,8 @@
+use semver::Version;
+
 pub const HASH_BYTE_SIZE: usize = 32;
+pub const CONTENT_CHUNK_SIZE: usize = 256 * 1024;
+pub const CONTENT_LINKS_PER_NODE: usize = 174;
+pub const SYMLINK_HOP_LIMIT: usize = 40;
+pub const METADATA_VERSION: Version = Version::new(2, 0, 0);
+pub const REGISTRY_VERSION: Version = Version::new(1, 0, 0);
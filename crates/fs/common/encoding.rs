@@ -11,7 +11,7 @@ pub mod dagcbor {
     };
     use serde::{de::DeserializeOwned, Serialize};
 
-    use crate::{AsyncSerialize, BlockStore};
+    use crate::{from_ipld, AsyncSerialize, BlockStore};
 
     /// Encodes a serializable value into DagCbor bytes.
     pub fn encode<S: Serialize>(value: &S) -> Result<Vec<u8>> {
@@ -33,8 +33,11 @@ pub mod dagcbor {
     }
 
     /// Decodes recieved DagCbor bytes into a deserializable value.
+    ///
+    /// Fails with [`FsError::UnsupportedVersion`](crate::FsError::UnsupportedVersion) if the value was encoded
+    /// by an unsupported major version.
     pub fn decode<D: DeserializeOwned>(bytes: &[u8]) -> Result<D> {
         let ipld = Ipld::decode(DagCborCodec, &mut Cursor::new(bytes))?;
-        Ok(ipld_serde::from_ipld::<_>(ipld)?)
+        from_ipld(ipld)
     }
 }
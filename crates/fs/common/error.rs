@@ -3,6 +3,7 @@
 use std::error::Error;
 
 use anyhow::Result;
+use semver::Version;
 use thiserror::Error;
 
 /// File system errors.
@@ -12,18 +13,48 @@ pub enum FsError {
     CIDNotFoundInBlockstore,
     #[error("Invalid WNFS path")]
     InvalidPath,
+    #[error("Invalid WNFS path segment: {0:?}")]
+    InvalidPathSegment(String),
     #[error("Expected a file")]
     NotAFile,
     #[error("Expected a directory")]
     NotADirectory,
+    #[error("Expected a symlink")]
+    NotASymlink,
     #[error("Cannot find file or directory")]
     NotFound,
     #[error("File already exists")]
     FileAlreadyExists,
     #[error("Directory already exists")]
     DirectoryAlreadyExists,
+    #[error("Directory is not empty")]
+    DirectoryNotEmpty,
     #[error("Move operation on invalid path")]
     InvalidMoveLocation,
+    #[error("Invalid glob pattern: {0}")]
+    InvalidGlobPattern(String),
+    #[error("Invalid permission mode: {0:o}")]
+    InvalidMode(u32),
+    #[error("Invalid extended attribute name: {0:?}")]
+    InvalidXattrName(String),
+    #[error("Too many levels of symlinks")]
+    SymlinkLoop,
+    #[error("Cannot find a matching revision")]
+    RevisionNotFound,
+    #[error("Replayed operation doesn't produce the recorded root")]
+    JournalMismatch,
+    #[error("Invalid snapshot or branch name: {0:?}")]
+    InvalidRefName(String),
+    #[error("Snapshot or branch already exists: {0:?}")]
+    RefAlreadyExists(String),
+    #[error("Cannot find snapshot or branch: {0:?}")]
+    RefNotFound(String),
+    #[error("Expected a branch: {0:?}")]
+    NotABranch(String),
+    #[error("Not a fast-forward of branch {0:?}")]
+    NotAFastForward(String),
+    #[error("Unsupported {0} version {1}")]
+    UnsupportedVersion(String, Version),
     #[error("Cannot decide cbor data")]
     UndecodableCborData(String),
 }
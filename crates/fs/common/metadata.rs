@@ -1,13 +1,24 @@
 //! File system metadata.
 
-use std::str::FromStr;
+use std::{collections::BTreeMap, fmt};
 
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use libipld::Ipld;
 use semver::Version;
-use serde::{Deserialize, Serialize};
-use serde_repr::{Deserialize_repr, Serialize_repr};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{error, migrate, FsError, Migration, METADATA_VERSION};
+
+//--------------------------------------------------------------------------------------------------
+// Constants
+//--------------------------------------------------------------------------------------------------
+
+/// The changes to the metadata encoding, sorted by the major version that introduced them.
+const METADATA_MIGRATIONS: &[Migration] = &[Migration {
+    major: 2,
+    upgrade: utils::upgrade_legacy_mode,
+}];
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
@@ -28,91 +39,278 @@ pub enum UnixFsNodeKind {
 
 /// Mode represents the Unix permissions for a UnixFS node.
 ///
+/// It holds the 12 permission bits of a POSIX mode: setuid, setgid and sticky, followed by read, write and execute
+/// for the owner, the group and others.
+///
 /// See
 /// - <https://docs.ipfs.io/concepts/file-systems/#unix-file-system-unixfs>
 /// - <https://en.wikipedia.org/wiki/File-system_permissions#Numeric_notation>
-#[derive(Debug, Clone, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
-#[repr(u32)]
-pub enum UnixFsMode {
-    NoPermissions = 0,
-    OwnerReadWriteExecute = 700,
-    OwnerGroupReadWriteExecute = 770,
-    AllReadWriteExecute = 777,
-    AllExecute = 111,
-    AllWrite = 222,
-    AllWriteExecute = 333,
-    AllRead = 444,
-    AllReadExecute = 555,
-    AllReadWrite = 666,
-    OwnerReadWriteExecuteGroupRead = 740,
-    OwnerReadWriteExecuteGroupOthersReadExecute = 755,
-    OwnerReadWriteGroupOthersRead = 644,
-}
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::UnixFsMode;
+///
+/// let mode = UnixFsMode::new(0o4755).unwrap();
+///
+/// assert!(mode.is_setuid());
+/// assert_eq!(mode.to_string(), "4755");
+/// assert!(UnixFsMode::new(0o17777).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct UnixFsMode(u16);
 
 /// The metadata of a node in the UnixFS file system.
 ///
+/// Timestamps are stored as whole seconds since the Unix epoch. The sub-second part is stored separately as
+/// nanoseconds and left out when it is zero, so readers that only know about seconds can still decode it.
+///
 /// See <https://docs.ipfs.io/concepts/file-systems/#unix-file-system-unixfs>
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct UnixFsMetadata {
     pub(crate) created: i64,
     pub(crate) modified: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) created_nanos: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) modified_nanos: Option<u32>,
     pub(crate) mode: UnixFsMode,
     pub(crate) kind: UnixFsNodeKind,
 }
 
 /// The metadata of a node on the WNFS file system.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Besides the UnixFS metadata, a node can hold extended attributes. These are named IPLD values that
+/// applications can use to attach their own data to a node.
+///
+/// Files record the byte size of their content. Directories record the total content size of the files
+/// below them, how many nodes are below them and how many of the files below them have an unknown size.
+/// Sizes are unknown for files whose content was not in the store when they were written, and the total
+/// size of a directory is unknown while it has such files below it. Directories encoded before sizes were
+/// tracked get them from their entries the first time they are changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Metadata {
     pub(crate) unix_fs: UnixFsMetadata,
     pub(crate) version: Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) descendants: Option<u64>,
+    #[serde(skip_serializing_if = "utils::is_zero")]
+    pub(crate) unknown_sizes: u64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) xattrs: BTreeMap<String, Ipld>,
 }
 
 //--------------------------------------------------------------------------------------------------
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
+impl UnixFsMode {
+    /// The bits a mode can have set.
+    pub const MASK: u32 = 0o7777;
+    /// Executables run as the owner of the file.
+    pub const SETUID: u32 = 0o4000;
+    /// Executables run as the group of the file and new nodes in a directory inherit its group.
+    pub const SETGID: u32 = 0o2000;
+    /// Nodes in a directory can only be removed or renamed by their owner.
+    pub const STICKY: u32 = 0o1000;
+
+    /// Creates a mode from its permission bits, failing if any bit outside of the 12 mode bits is set.
+    pub fn new(bits: u32) -> Result<Self> {
+        if bits & !Self::MASK != 0 {
+            return error(FsError::InvalidMode(bits));
+        }
+
+        Ok(Self(bits as u16))
+    }
+
+    /// Gets the permission bits.
+    pub fn bits(&self) -> u32 {
+        u32::from(self.0)
+    }
+
+    /// Checks if the setuid bit is set.
+    pub fn is_setuid(&self) -> bool {
+        self.bits() & Self::SETUID != 0
+    }
+
+    /// Checks if the setgid bit is set.
+    pub fn is_setgid(&self) -> bool {
+        self.bits() & Self::SETGID != 0
+    }
+
+    /// Checks if the sticky bit is set.
+    pub fn is_sticky(&self) -> bool {
+        self.bits() & Self::STICKY != 0
+    }
+
+    /// Converts a mode from the encoding used by metadata before version 2.
+    ///
+    /// That encoding stored the octal digits of a mode as a decimal number, so `0o755` was stored as `755`.
+    pub(crate) fn from_legacy(num: u32) -> Result<Self, String> {
+        let digits = num.to_string();
+        u32::from_str_radix(&digits, 8)
+            .ok()
+            .filter(|bits| bits & !Self::MASK == 0)
+            .map(|bits| Self(bits as u16))
+            .ok_or_else(|| format!("Unknown UnixFsMode: {}", num))
+    }
+}
+
+impl fmt::Display for UnixFsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
 impl Metadata {
     /// Creates a new metadata representing a UnixFS node.
     pub fn new(time: DateTime<Utc>, kind: UnixFsNodeKind) -> Self {
         let mode =
             if matches!(kind, UnixFsNodeKind::Dir) || matches!(kind, UnixFsNodeKind::HAMTShard) {
-                UnixFsMode::OwnerReadWriteGroupOthersRead
+                UnixFsMode(0o644)
             } else {
-                UnixFsMode::OwnerReadWriteExecuteGroupOthersReadExecute
+                UnixFsMode(0o755)
             };
 
-        let time = time.timestamp();
+        let (secs, nanos) = utils::split_time(time);
+        let (size, descendants) = match kind {
+            UnixFsNodeKind::Dir => (Some(0), Some(0)),
+            _ => (None, None),
+        };
 
         Self {
             unix_fs: UnixFsMetadata {
-                created: time,
-                modified: time,
+                created: secs,
+                modified: secs,
+                created_nanos: nanos,
+                modified_nanos: nanos,
                 mode,
                 kind,
             },
-            version: Version::new(1, 0, 0),
+            version: METADATA_VERSION,
+            size,
+            descendants,
+            unknown_sizes: 0,
+            xattrs: BTreeMap::new(),
         }
     }
 
     pub fn is_file(&self) -> bool {
         matches!(self.unix_fs.kind, UnixFsNodeKind::File)
     }
+
+    pub fn is_symlink(&self) -> bool {
+        matches!(self.unix_fs.kind, UnixFsNodeKind::SymLink)
+    }
+
+    /// Gets the time the node was created.
+    pub fn get_created(&self) -> DateTime<Utc> {
+        Utc.timestamp(
+            self.unix_fs.created,
+            self.unix_fs.created_nanos.unwrap_or_default(),
+        )
+    }
+
+    /// Gets the time the node was last modified.
+    pub fn get_modified(&self) -> DateTime<Utc> {
+        Utc.timestamp(
+            self.unix_fs.modified,
+            self.unix_fs.modified_nanos.unwrap_or_default(),
+        )
+    }
+
+    /// Sets the time the node was created.
+    pub fn set_created(&mut self, time: DateTime<Utc>) {
+        (self.unix_fs.created, self.unix_fs.created_nanos) = utils::split_time(time);
+    }
+
+    /// Sets the time the node was last modified.
+    pub fn set_modified(&mut self, time: DateTime<Utc>) {
+        (self.unix_fs.modified, self.unix_fs.modified_nanos) = utils::split_time(time);
+    }
+
+    /// Gets the byte size of a file's content or the total content size of the files below a directory.
+    pub fn get_size(&self) -> Option<u64> {
+        self.size.filter(|_| self.unknown_sizes == 0)
+    }
+
+    /// Gets the number of nodes below a directory.
+    pub fn get_descendants(&self) -> Option<u64> {
+        self.descendants
+    }
+
+    /// Gets the permissions of the node.
+    pub fn get_mode(&self) -> UnixFsMode {
+        self.unix_fs.mode
+    }
+
+    /// Gets the value of an extended attribute.
+    pub fn get_xattr(&self, name: &str) -> Option<&Ipld> {
+        self.xattrs.get(name)
+    }
+
+    /// Sets the value of an extended attribute and returns the value it replaced.
+    pub fn set_xattr(&mut self, name: &str, value: Ipld) -> Result<Option<Ipld>> {
+        if name.is_empty() {
+            return error(FsError::InvalidXattrName(name.to_string()));
+        }
+
+        Ok(self.xattrs.insert(name.to_string(), value))
+    }
+
+    /// Removes an extended attribute and returns its value.
+    pub fn remove_xattr(&mut self, name: &str) -> Option<Ipld> {
+        self.xattrs.remove(name)
+    }
+
+    /// Gets the names of the extended attributes in sorted order.
+    pub fn list_xattrs(&self) -> impl Iterator<Item = &str> {
+        self.xattrs.keys().map(String::as_str)
+    }
+}
+
+impl<'de> Deserialize<'de> for Metadata {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ipld::deserialize(deserializer)
+            .and_then(|ipld| (&ipld).try_into().map_err(de::Error::custom))
+    }
 }
 
 impl TryFrom<&Ipld> for Metadata {
     type Error = String;
 
+    /// Decodes metadata, upgrading it from the version it was encoded with to [`METADATA_VERSION`].
     fn try_from(ipld: &Ipld) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Map(map) => {
-                let unix_fs = map.get("unix_fs").ok_or("Missing unix_fs")?.try_into()?;
+                let mut map = map.clone();
+                migrate("metadata", &mut map, &METADATA_VERSION, METADATA_MIGRATIONS)?;
 
-                let version = match map.get("version").ok_or("Missing version")? {
-                    Ipld::String(v) => Version::from_str(v).map_err(|e| e.to_string())?,
-                    _ => return Err("version is not a string".into()),
+                let unix_fs = map.get("unix_fs").ok_or("Missing unix_fs")?.try_into()?;
+                let size = utils::u64_from_ipld(map.get("size"))?;
+                let descendants = utils::u64_from_ipld(map.get("descendants"))?;
+                let unknown_sizes = utils::u64_from_ipld(map.get("unknown_sizes"))?;
+
+                let xattrs = match map.remove("xattrs") {
+                    Some(Ipld::Map(xattrs)) => xattrs,
+                    Some(_) => return Err("`xattrs` is not a map".into()),
+                    None => BTreeMap::new(),
                 };
 
-                Ok(Metadata { unix_fs, version })
+                Ok(Metadata {
+                    unix_fs,
+                    version: METADATA_VERSION,
+                    size,
+                    descendants,
+                    unknown_sizes: unknown_sizes.unwrap_or_default(),
+                    xattrs,
+                })
             }
             other => Err(format!("Expected `Ipld::Map` got {:#?}", other)),
         }
@@ -135,12 +333,16 @@ impl TryFrom<&Ipld> for UnixFsMetadata {
                     _ => return Err("`modified` is not an integer".into()),
                 };
 
+                let created_nanos = utils::nanos_from_ipld(map.get("created_nanos"), created)?;
+                let modified_nanos = utils::nanos_from_ipld(map.get("modified_nanos"), modified)?;
                 let mode = map.get("mode").ok_or("Missing mode")?.try_into()?;
                 let kind = map.get("kind").ok_or("Missing kind")?.try_into()?;
 
                 Ok(UnixFsMetadata {
                     created,
                     modified,
+                    created_nanos,
+                    modified_nanos,
                     mode,
                     kind,
                 })
@@ -155,7 +357,10 @@ impl TryFrom<&Ipld> for UnixFsMode {
 
     fn try_from(ipld: &Ipld) -> Result<Self, Self::Error> {
         match ipld {
-            Ipld::Integer(i) => UnixFsMode::try_from(*i as u32),
+            Ipld::Integer(i) => {
+                let num = u32::try_from(*i).map_err(|e| e.to_string())?;
+                UnixFsMode::try_from(num)
+            }
             other => Err(format!("Expected `Ipld::Integer` got {:#?}", other)),
         }
     }
@@ -165,22 +370,7 @@ impl TryFrom<u32> for UnixFsMode {
     type Error = String;
 
     fn try_from(num: u32) -> Result<Self, Self::Error> {
-        Ok(match num {
-            0 => UnixFsMode::NoPermissions,
-            700 => UnixFsMode::OwnerReadWriteExecute,
-            770 => UnixFsMode::OwnerGroupReadWriteExecute,
-            777 => UnixFsMode::AllReadWriteExecute,
-            111 => UnixFsMode::AllExecute,
-            222 => UnixFsMode::AllWrite,
-            333 => UnixFsMode::AllWriteExecute,
-            444 => UnixFsMode::AllRead,
-            555 => UnixFsMode::AllReadExecute,
-            666 => UnixFsMode::AllReadWrite,
-            740 => UnixFsMode::OwnerReadWriteExecuteGroupRead,
-            755 => UnixFsMode::OwnerReadWriteExecuteGroupOthersReadExecute,
-            644 => UnixFsMode::OwnerReadWriteGroupOthersRead,
-            _ => return Err(format!("Unknown UnixFsMode: {}", num)),
-        })
+        UnixFsMode::new(num).map_err(|e| e.to_string())
     }
 }
 
@@ -200,23 +390,85 @@ impl TryFrom<&str> for UnixFsNodeKind {
 
     fn try_from(name: &str) -> Result<Self, Self::Error> {
         Ok(match name.to_lowercase().as_str() {
+            "raw" => UnixFsNodeKind::Raw,
             "file" => UnixFsNodeKind::File,
             "dir" => UnixFsNodeKind::Dir,
-            "hamt-shard" => UnixFsNodeKind::HAMTShard,
+            "metadata" => UnixFsNodeKind::Metadata,
+            "symlink" => UnixFsNodeKind::SymLink,
+            "hamt-shard" | "hamtshard" => UnixFsNodeKind::HAMTShard,
             _ => return Err(format!("Unknown UnixFsNodeKind: {}", name)),
         })
     }
 }
 
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use super::*;
+
+    /// Splits a time into seconds since the Unix epoch and nanoseconds, leaving out zero nanoseconds.
+    pub(super) fn split_time(time: DateTime<Utc>) -> (i64, Option<u32>) {
+        let nanos = time.timestamp_subsec_nanos();
+        (time.timestamp(), Some(nanos).filter(|nanos| *nanos != 0))
+    }
+
+    /// Converts the mode of metadata encoded before version 2 from octal digits stored as a decimal number.
+    pub(super) fn upgrade_legacy_mode(map: &mut BTreeMap<String, Ipld>) -> Result<(), String> {
+        if let Some(Ipld::Map(unix_fs)) = map.get_mut("unix_fs") {
+            if let Some(Ipld::Integer(mode)) = unix_fs.get_mut("mode") {
+                let num = u32::try_from(*mode).map_err(|e| e.to_string())?;
+                *mode = UnixFsMode::from_legacy(num)?.bits().into();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a count is zero, so that it can be left out of the encoding.
+    pub(super) fn is_zero(count: &u64) -> bool {
+        *count == 0
+    }
+
+    /// Decodes an optional unsigned integer.
+    pub(super) fn u64_from_ipld(ipld: Option<&Ipld>) -> Result<Option<u64>, String> {
+        match ipld {
+            Some(Ipld::Integer(i)) => Ok(Some(u64::try_from(*i).map_err(|e| e.to_string())?)),
+            Some(other) => Err(format!("Expected `Ipld::Integer` got {:#?}", other)),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the optional nanoseconds of a timestamp with the given seconds, checking that the timestamp
+    /// is in range.
+    pub(super) fn nanos_from_ipld(ipld: Option<&Ipld>, secs: i64) -> Result<Option<u32>, String> {
+        let nanos = match ipld {
+            Some(Ipld::Integer(i)) => Some(u32::try_from(*i).map_err(|e| e.to_string())?),
+            Some(_) => return Err("nanoseconds are not an integer".into()),
+            None => None,
+        };
+
+        match NaiveDateTime::from_timestamp_opt(secs, nanos.unwrap_or_default()) {
+            Some(_) => Ok(nanos),
+            None => Err(format!(
+                "Invalid timestamp: {secs}s {}ns",
+                nanos.unwrap_or_default()
+            )),
+        }
+    }
+}
+
 //--------------------------------------------------------------------------------------------------
 // Tests
 //--------------------------------------------------------------------------------------------------
 
 #[cfg(test)]
 mod metadata_tests {
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
+    use libipld::Ipld;
 
-    use crate::{dagcbor, Metadata, UnixFsNodeKind};
+    use crate::{dagcbor, Metadata, UnixFsMode, UnixFsNodeKind};
 
     #[async_std::test]
     async fn metadata_can_encode_decode_as_cbor() {
@@ -227,4 +479,123 @@ mod metadata_tests {
 
         assert_eq!(metadata, decoded_metadata);
     }
+
+    #[async_std::test]
+    async fn special_mode_bits_survive_encoding() {
+        let mut metadata = Metadata::new(Utc::now(), UnixFsNodeKind::Dir);
+        metadata.unix_fs.mode = UnixFsMode::new(0o7751).unwrap();
+
+        let encoded_metadata = dagcbor::encode(&metadata).unwrap();
+        let decoded_metadata = dagcbor::decode::<Metadata>(encoded_metadata.as_ref()).unwrap();
+
+        assert_eq!(decoded_metadata.get_mode().bits(), 0o7751);
+        assert!(decoded_metadata.get_mode().is_setuid());
+        assert!(decoded_metadata.get_mode().is_setgid());
+        assert!(decoded_metadata.get_mode().is_sticky());
+    }
+
+    #[async_std::test]
+    async fn legacy_modes_are_decoded_as_octal_digits() {
+        let legacy_metadata = |mode: i128| {
+            Ipld::Map(
+                [
+                    (
+                        "unix_fs".to_string(),
+                        Ipld::Map(
+                            [
+                                ("created".to_string(), Ipld::Integer(0)),
+                                ("modified".to_string(), Ipld::Integer(0)),
+                                ("mode".to_string(), Ipld::Integer(mode)),
+                                ("kind".to_string(), Ipld::String("File".into())),
+                            ]
+                            .into(),
+                        ),
+                    ),
+                    ("version".to_string(), Ipld::String("1.0.0".into())),
+                ]
+                .into(),
+            )
+        };
+
+        let metadata = Metadata::try_from(&legacy_metadata(755)).unwrap();
+        assert_eq!(metadata.get_mode().bits(), 0o755);
+
+        let metadata = Metadata::try_from(&legacy_metadata(640)).unwrap();
+        assert_eq!(metadata.get_mode().bits(), 0o640);
+
+        assert!(Metadata::try_from(&legacy_metadata(789)).is_err());
+    }
+
+    #[async_std::test]
+    async fn sub_second_times_survive_encoding() {
+        let time = Utc.timestamp(1_650_000_000, 123_456_789);
+        let mut metadata = Metadata::new(time, UnixFsNodeKind::File);
+        metadata.set_created(Utc.timestamp(1_600_000_000, 0));
+
+        let encoded_metadata = dagcbor::encode(&metadata).unwrap();
+        let decoded_metadata = dagcbor::decode::<Metadata>(encoded_metadata.as_ref()).unwrap();
+
+        assert_eq!(decoded_metadata.get_modified(), time);
+        assert_eq!(
+            decoded_metadata.get_created(),
+            Utc.timestamp(1_600_000_000, 0)
+        );
+        assert_eq!(decoded_metadata.unix_fs.created_nanos, None);
+        assert_eq!(decoded_metadata.unix_fs.modified, 1_650_000_000);
+    }
+
+    #[async_std::test]
+    async fn out_of_range_times_are_rejected_without_nanos() {
+        let metadata = |created: i128, modified: i128| {
+            Ipld::Map(
+                [
+                    (
+                        "unix_fs".to_string(),
+                        Ipld::Map(
+                            [
+                                ("created".to_string(), Ipld::Integer(created)),
+                                ("modified".to_string(), Ipld::Integer(modified)),
+                                ("mode".to_string(), Ipld::Integer(0o644)),
+                                ("kind".to_string(), Ipld::String("File".into())),
+                            ]
+                            .into(),
+                        ),
+                    ),
+                    ("version".to_string(), Ipld::String("2.0.0".into())),
+                ]
+                .into(),
+            )
+        };
+
+        assert!(Metadata::try_from(&metadata(0, 1_650_000_000)).is_ok());
+        assert!(Metadata::try_from(&metadata(i64::MAX.into(), 0)).is_err());
+        assert!(Metadata::try_from(&metadata(0, i64::MIN.into())).is_err());
+    }
+
+    #[async_std::test]
+    async fn xattrs_survive_encoding() {
+        let mut metadata = Metadata::new(Utc::now(), UnixFsNodeKind::File);
+
+        let encoded_metadata = dagcbor::encode(&metadata).unwrap();
+        let decoded_ipld = dagcbor::decode::<Ipld>(encoded_metadata.as_ref()).unwrap();
+
+        assert!(matches!(decoded_ipld, Ipld::Map(map) if !map.contains_key("xattrs")));
+
+        metadata
+            .set_xattr("content-type", Ipld::String("image/png".into()))
+            .unwrap();
+        metadata
+            .set_xattr("acl", Ipld::List(vec![Ipld::String("alice".into())]))
+            .unwrap();
+
+        let encoded_metadata = dagcbor::encode(&metadata).unwrap();
+        let decoded_metadata = dagcbor::decode::<Metadata>(encoded_metadata.as_ref()).unwrap();
+
+        assert_eq!(metadata, decoded_metadata);
+        assert_eq!(
+            decoded_metadata.list_xattrs().collect::<Vec<_>>(),
+            ["acl", "content-type"]
+        );
+        assert!(metadata.set_xattr("", Ipld::Null).is_err());
+    }
 }
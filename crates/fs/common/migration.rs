@@ -0,0 +1,155 @@
+//! Upgrades of older node encodings.
+
+use std::{cell::RefCell, collections::BTreeMap, str::FromStr};
+
+use anyhow::Result;
+use libipld::{serde as ipld_serde, Ipld};
+use semver::Version;
+use serde::de::DeserializeOwned;
+
+use crate::FsError;
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A change to the encoding of a versioned value, introduced in a major version.
+///
+/// Migrations rewrite the IPLD map of a value encoded by an older major version into the layout expected by
+/// the major version that introduced the change. Values are decoded only after every migration between their
+/// version and the current one has been applied.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// The major version that introduced the change.
+    pub major: u64,
+    /// Rewrites a map encoded before `major` into the layout of `major`.
+    pub upgrade: fn(&mut BTreeMap<String, Ipld>) -> Result<(), String>,
+}
+
+thread_local! {
+    /// The last unsupported version rejected by [`migrate`], kept so that [`from_ipld`] can return it as an
+    /// [`FsError`] once serde has turned it into a message.
+    static UNSUPPORTED_VERSION: RefCell<Option<FsError>> = const { RefCell::new(None) };
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Gets the version a versioned value was encoded with.
+pub fn get_version(map: &BTreeMap<String, Ipld>) -> Result<Version, String> {
+    match map.get("version").ok_or("Missing version")? {
+        Ipld::String(v) => Version::from_str(v).map_err(|e| e.to_string()),
+        _ => Err("`version` is not a string".into()),
+    }
+}
+
+/// Upgrades the map of a value of the given kind from the version it was encoded with to `current`.
+///
+/// Values from a newer major version than `current` can't be read safely, so they are rejected with the
+/// message of [`FsError::UnsupportedVersion`]. Values decoded from a block store or with
+/// [`dagcbor::decode`](crate::dagcbor::decode) fail with the error itself. Migrations must be sorted by
+/// major version.
+pub fn migrate(
+    kind: &str,
+    map: &mut BTreeMap<String, Ipld>,
+    current: &Version,
+    migrations: &[Migration],
+) -> Result<Version, String> {
+    let version = get_version(map)?;
+    if version.major > current.major {
+        let error = FsError::UnsupportedVersion(kind.to_string(), version);
+        let message = error.to_string();
+        UNSUPPORTED_VERSION.with(|unsupported| *unsupported.borrow_mut() = Some(error));
+        return Err(message);
+    }
+
+    for migration in migrations {
+        if version.major < migration.major && migration.major <= current.major {
+            (migration.upgrade)(map)?;
+        }
+    }
+
+    Ok(version)
+}
+
+/// Decodes a value from IPLD.
+///
+/// Fails with [`FsError::UnsupportedVersion`] if the value, or one nested in it, was encoded by an unsupported
+/// major version.
+pub(crate) fn from_ipld<D: DeserializeOwned>(ipld: Ipld) -> Result<D> {
+    UNSUPPORTED_VERSION.with(|unsupported| unsupported.take());
+
+    ipld_serde::from_ipld(ipld).map_err(|e| {
+        match UNSUPPORTED_VERSION.with(|unsupported| unsupported.take()) {
+            Some(error) => error.into(),
+            None => e.into(),
+        }
+    })
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod migration_tests {
+    use super::*;
+
+    fn versioned(version: &str) -> BTreeMap<String, Ipld> {
+        BTreeMap::from([
+            ("version".into(), Ipld::String(version.into())),
+            ("steps".into(), Ipld::List(vec![])),
+        ])
+    }
+
+    fn record(map: &mut BTreeMap<String, Ipld>, major: i128) -> Result<(), String> {
+        match map.get_mut("steps") {
+            Some(Ipld::List(steps)) => {
+                steps.push(Ipld::Integer(major));
+                Ok(())
+            }
+            _ => Err("Missing steps".into()),
+        }
+    }
+
+    #[test]
+    fn migrations_are_applied_in_order_up_to_the_current_version() {
+        let migrations = [
+            Migration {
+                major: 2,
+                upgrade: |map| record(map, 2),
+            },
+            Migration {
+                major: 3,
+                upgrade: |map| record(map, 3),
+            },
+        ];
+
+        let mut map = versioned("1.2.0");
+        let version = migrate("test", &mut map, &Version::new(3, 0, 0), &migrations).unwrap();
+
+        assert_eq!(version, Version::new(1, 2, 0));
+        assert_eq!(
+            map["steps"],
+            Ipld::List(vec![Ipld::Integer(2), Ipld::Integer(3)])
+        );
+
+        let mut map = versioned("2.0.0");
+        migrate("test", &mut map, &Version::new(2, 1, 0), &migrations).unwrap();
+
+        assert_eq!(map["steps"], Ipld::List(vec![]));
+    }
+
+    #[test]
+    fn future_major_versions_are_rejected() {
+        let mut map = versioned("3.0.0");
+        let error = migrate("test", &mut map, &Version::new(2, 5, 0), &[]).unwrap_err();
+
+        assert_eq!(
+            error,
+            FsError::UnsupportedVersion("test".into(), Version::new(3, 0, 0)).to_string()
+        );
+        assert!(migrate("test", &mut versioned("2.9.0"), &Version::new(2, 5, 0), &[]).is_ok());
+    }
+}
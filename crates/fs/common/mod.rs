@@ -4,6 +4,8 @@ mod encoding;
 mod error;
 pub mod link;
 mod metadata;
+mod migration;
+mod path;
 
 pub use blockstore::*;
 pub use constants::*;
@@ -11,6 +13,8 @@ pub use encoding::*;
 pub use error::*;
 pub use link::*;
 pub use metadata::*;
+pub use migration::*;
+pub use path::*;
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
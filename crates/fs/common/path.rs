@@ -0,0 +1,249 @@
+//! File system paths.
+
+use std::{fmt, ops::Deref, str::FromStr};
+
+use anyhow::Result;
+
+use crate::{error, FsError};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A validated path in a WNFS file system.
+///
+/// A path is a list of segments that are never empty, never `.` or `..`, and never contain a `/` or a
+/// null character. It dereferences to `[String]`, so it can be passed to any operation that takes path
+/// segments. Operations check raw segments the same way and fail with [`FsError::InvalidPathSegment`].
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore, WnfsPath};
+/// use std::rc::Rc;
+/// use chrono::Utc;
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = MemoryBlockStore::default();
+///     let path: WnfsPath = "/pictures/./cats/../dogs".parse().unwrap();
+///
+///     assert_eq!(path.to_string(), "/pictures/dogs");
+///
+///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+///         .mkdir(&path, Utc::now(), &store)
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WnfsPath(Vec<String>);
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl WnfsPath {
+    /// Creates a path from its segments, checking that each one is a valid name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::WnfsPath;
+    ///
+    /// assert!(WnfsPath::new(vec!["pictures".into(), "cats".into()]).is_ok());
+    /// assert!(WnfsPath::new(vec!["pictures/cats".into()]).is_err());
+    /// ```
+    pub fn new(segments: Vec<String>) -> Result<Self> {
+        validate_segments(&segments)?;
+        Ok(Self(segments))
+    }
+
+    /// Creates the path of the root directory.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Checks if this is the path of the root directory.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Creates a path with a valid name appended to this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::WnfsPath;
+    ///
+    /// let path = WnfsPath::root().join("pictures").unwrap();
+    ///
+    /// assert_eq!(path.to_string(), "/pictures");
+    /// assert!(path.join("..").is_err());
+    /// ```
+    pub fn join(&self, name: &str) -> Result<Self> {
+        utils::validate_segment(name)?;
+
+        let mut segments = self.0.clone();
+        segments.push(name.to_string());
+        Ok(Self(segments))
+    }
+
+    /// Gets the path of the parent directory. The root directory has no parent.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Self(parent.to_vec()))
+    }
+
+    /// Gets the last segment of the path. The root directory has no name.
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    /// Converts the path into its segments.
+    pub fn into_segments(self) -> Vec<String> {
+        self.0
+    }
+}
+
+impl Deref for WnfsPath {
+    type Target = [String];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<[String]> for WnfsPath {
+    fn as_ref(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl FromStr for WnfsPath {
+    type Err = anyhow::Error;
+
+    /// Parses a slash-separated path.
+    ///
+    /// Leading, trailing and repeated slashes are ignored, `.` segments are dropped and `..` segments remove
+    /// the segment before them. Going above the root is an error.
+    fn from_str(path: &str) -> Result<Self> {
+        let mut segments: Vec<String> = vec![];
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => (),
+                ".." => {
+                    if segments.pop().is_none() {
+                        return error(FsError::InvalidPath);
+                    }
+                }
+                name => {
+                    utils::validate_segment(name)?;
+                    segments.push(name.to_string());
+                }
+            }
+        }
+
+        Ok(Self(segments))
+    }
+}
+
+impl fmt::Display for WnfsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+
+        for segment in self.0.iter() {
+            write!(f, "/{segment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Checks that each segment of a path is a valid name, as in a [`WnfsPath`].
+pub(crate) fn validate_segments(path_segments: &[String]) -> Result<()> {
+    for segment in path_segments {
+        utils::validate_segment(segment)?;
+    }
+
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use anyhow::Result;
+
+    use crate::{error, FsError};
+
+    pub(super) fn validate_segment(segment: &str) -> Result<()> {
+        if segment.is_empty() || segment == "." || segment == ".." || segment.contains(['/', '\0'])
+        {
+            return error(FsError::InvalidPathSegment(segment.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod path_tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_parsed_and_normalized() {
+        let cases = [
+            ("/a/b/c", vec!["a", "b", "c"]),
+            ("a//b/", vec!["a", "b"]),
+            ("/a/./b/../c", vec!["a", "c"]),
+            ("/", vec![]),
+            ("", vec![]),
+        ];
+
+        for (path, expected) in cases {
+            let parsed: WnfsPath = path.parse().unwrap();
+            assert_eq!(&*parsed, expected, "{path}");
+        }
+
+        assert!("/a/../..".parse::<WnfsPath>().is_err());
+        assert!("/a/\0b".parse::<WnfsPath>().is_err());
+    }
+
+    #[test]
+    fn invalid_segments_are_rejected() {
+        for segment in ["", ".", "..", "a/b", "a\0b"] {
+            let error = WnfsPath::new(vec![segment.into()]).unwrap_err();
+
+            assert_eq!(
+                error.downcast_ref::<FsError>(),
+                Some(&FsError::InvalidPathSegment(segment.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn paths_can_be_navigated_and_displayed() {
+        let path: WnfsPath = "/pictures/cats".parse().unwrap();
+
+        assert_eq!(path.name(), Some("cats"));
+        assert_eq!(path.parent().unwrap().to_string(), "/pictures");
+        assert_eq!(
+            path.join("tabby.png").unwrap().to_string(),
+            "/pictures/cats/tabby.png"
+        );
+        assert!(WnfsPath::root().parent().is_none());
+        assert_eq!(WnfsPath::root().to_string(), "/");
+    }
+}
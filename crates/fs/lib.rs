@@ -24,6 +24,6 @@ pub mod ipld {
         cbor::DagCborCodec,
         codec::Codec,
         codec::{Decode, Encode},
-        Cid, IpldCodec,
+        Cid, Ipld, IpldCodec,
     };
 }
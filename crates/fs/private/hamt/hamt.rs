@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, rc::Rc, str::FromStr};
+use std::{collections::BTreeMap, rc::Rc};
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -10,10 +10,17 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::{AsyncSerialize, BlockStore};
+use crate::{migrate, AsyncSerialize, BlockStore, Migration};
 
 use super::{Node, HAMT_VERSION};
 
+//--------------------------------------------------------------------------------------------------
+// Constants
+//--------------------------------------------------------------------------------------------------
+
+/// The changes to the HAMT encoding, sorted by the major version that introduced them.
+const HAMT_MIGRATIONS: &[Migration] = &[];
+
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
 //--------------------------------------------------------------------------------------------------
@@ -96,19 +103,17 @@ where
 {
     type Error = String;
 
+    /// Decodes a HAMT, upgrading it from the version it was encoded with to `HAMT_VERSION`.
     fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Map(mut map) => {
+                migrate("HAMT", &mut map, &HAMT_VERSION, HAMT_MIGRATIONS)?;
+
                 let root = Rc::new(
                     Node::<K, V>::deserialize(map.remove("root").ok_or("Missing root")?)
                         .map_err(|e| e.to_string())?,
                 );
 
-                let version = match map.get("version").ok_or("Missing version")? {
-                    Ipld::String(v) => Version::from_str(v).map_err(|e| e.to_string())?,
-                    _ => return Err("`version` is not a string".into()),
-                };
-
                 let structure = map
                     .get("structure")
                     .ok_or("Missing structure")?
@@ -116,7 +121,7 @@ where
 
                 Ok(Self {
                     root,
-                    version,
+                    version: HAMT_VERSION,
                     structure,
                 })
             }
@@ -155,7 +160,7 @@ impl TryFrom<&str> for Structure {
 mod hamt_tests {
 
     use super::*;
-    use crate::{dagcbor, MemoryBlockStore};
+    use crate::{dagcbor, FsError, MemoryBlockStore};
 
     #[async_std::test]
     async fn hamt_can_encode_decode_as_cbor() {
@@ -168,4 +173,26 @@ mod hamt_tests {
 
         assert_eq!(hamt, decoded_hamt);
     }
+
+    #[async_std::test]
+    async fn hamt_from_a_future_major_version_is_rejected() {
+        let store = &mut MemoryBlockStore::default();
+        let hamt: Hamt<String, i32> = Hamt::with_root(Rc::new(Node::default()));
+
+        let mut ipld = hamt.to_ipld(store).await.unwrap();
+        if let Ipld::Map(map) = &mut ipld {
+            map.insert("version".into(), Ipld::String("2.0.0".into()));
+        }
+
+        let encoded_hamt = dagcbor::encode(&ipld).unwrap();
+        let error = dagcbor::decode::<Hamt<String, i32>>(encoded_hamt.as_ref()).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::UnsupportedVersion(
+                "HAMT".into(),
+                Version::new(2, 0, 0)
+            ))
+        );
+    }
 }
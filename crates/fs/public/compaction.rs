@@ -0,0 +1,534 @@
+//! Compaction of the history of public trees.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libipld::Cid;
+
+use crate::BlockStore;
+
+use super::{OpResult, PublicDirectory, PublicLink, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Which revisions of a root directory [`PublicDirectory::compact_history`] keeps.
+///
+/// Revisions are the root directories reachable through `previous` links. The current revision is always
+/// kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    /// Keeps the given number of revisions that are the fewest `previous` links away from the current one.
+    KeepLast(usize),
+    /// Keeps the revision modified last on each day, for the given number of days up to and including the
+    /// day the current revision was modified.
+    DailyFor(u32),
+    /// Keeps the revisions modified at or after the given time.
+    DropBefore(DateTime<Utc>),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl RetentionPolicy {
+    /// Picks the revisions to keep. Revisions are given with the time they were modified, ordered by how
+    /// many `previous` links away from the current revision they are, starting with the current one.
+    fn select(&self, revisions: &[(Cid, DateTime<Utc>)]) -> BTreeSet<Cid> {
+        let mut kept = revisions
+            .iter()
+            .take(1)
+            .map(|(cid, _)| *cid)
+            .collect::<BTreeSet<_>>();
+
+        match self {
+            Self::KeepLast(count) => {
+                kept.extend(revisions.iter().take(*count).map(|(cid, _)| *cid))
+            }
+            Self::DailyFor(days) => {
+                let last_day = match revisions.first() {
+                    Some((_, modified)) => modified.date(),
+                    None => return kept,
+                };
+
+                let mut newest_per_day = BTreeMap::new();
+                for (cid, modified) in revisions {
+                    let age = (last_day - modified.date()).num_days();
+                    if age < 0 || age >= i64::from(*days) {
+                        continue;
+                    }
+
+                    let newest = newest_per_day
+                        .entry(modified.date())
+                        .or_insert((*modified, *cid));
+                    if *modified > newest.0 {
+                        *newest = (*modified, *cid);
+                    }
+                }
+
+                kept.extend(newest_per_day.into_values().map(|(_, cid)| cid));
+            }
+            Self::DropBefore(time) => kept.extend(
+                revisions
+                    .iter()
+                    .filter(|(_, modified)| modified >= time)
+                    .map(|(cid, _)| *cid),
+            ),
+        }
+
+        kept
+    }
+}
+
+impl PublicDirectory {
+    /// Drops the revisions of this root directory that the policy doesn't keep.
+    ///
+    /// The `previous` links of each kept revision are rewritten to point at the closest kept revisions
+    /// behind it. Nodes inside the kept revisions get the same treatment, so their `previous` links only lead
+    /// to nodes that are still part of a kept revision. The result is the set of CIDs that were reachable from
+    /// this directory before compaction but aren't from the compacted one. This includes nodes whose CID
+    /// changed because their links were rewritten, and file contents by their root CID. Nothing is removed
+    /// from the store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult, RetentionPolicy}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let base = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::clone(&base)
+    ///         .mkdir(&["pictures".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .base_history_on(Rc::clone(&base), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, result } = root_dir
+    ///         .compact_history(&RetentionPolicy::KeepLast(1), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(root_dir.get_previous().is_empty());
+    ///     assert!(result.contains(&base.store(&mut store).await.unwrap()));
+    /// }
+    /// ```
+    pub async fn compact_history<B: BlockStore>(
+        self: Rc<Self>,
+        policy: &RetentionPolicy,
+        store: &mut B,
+    ) -> Result<OpResult<BTreeSet<Cid>>> {
+        let head = self.store(store).await?;
+        let nodes = utils::load_nodes(head, store).await?;
+        let kept = utils::tree_nodes(&policy.select(&utils::revisions(head, &nodes)), &nodes);
+
+        // Every node is visited after the nodes it links to, so links can be rewritten as they are found.
+        let mut rewritten = BTreeMap::new();
+        let mut previous_links = BTreeMap::<Cid, BTreeSet<Cid>>::new();
+        for cid in utils::post_order(head, &nodes) {
+            let node = &nodes[&cid];
+            let previous = node
+                .get_previous()
+                .iter()
+                .flat_map(|previous| previous_links[previous].iter().copied())
+                .collect::<BTreeSet<_>>();
+
+            if !kept.contains(&cid) {
+                previous_links.insert(cid, previous);
+                continue;
+            }
+
+            let new_cid = match node {
+                PublicNode::Dir(dir) => {
+                    let mut dir = (**dir).clone();
+                    for link in dir.userland.values_mut() {
+                        let entry = *link.resolve_cid(store).await?;
+                        *link = PublicLink::from_cid(rewritten[&entry]);
+                    }
+
+                    dir.previous = previous;
+                    dir.store(store).await?
+                }
+                _ => node.update_previous(previous).store(store).await?,
+            };
+
+            rewritten.insert(cid, new_cid);
+            previous_links.insert(cid, BTreeSet::from([new_cid]));
+        }
+
+        let reachable = rewritten
+            .values()
+            .copied()
+            .chain(kept.iter().filter_map(|cid| utils::content(&nodes[cid])))
+            .collect::<BTreeSet<_>>();
+
+        let unreachable = nodes
+            .iter()
+            .flat_map(|(cid, node)| [Some(*cid), utils::content(node)])
+            .flatten()
+            .filter(|cid| !reachable.contains(cid))
+            .collect();
+
+        Ok(OpResult {
+            root_dir: Rc::new(store.get_deserializable(&rewritten[&head]).await?),
+            result: unreachable,
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+    use anyhow::Result;
+    use chrono::{DateTime, Utc};
+    use libipld::Cid;
+
+    use crate::BlockStore;
+
+    use super::PublicNode;
+
+    /// Gets the CIDs of the entries of a directory. Other nodes have none.
+    pub(super) fn entries(node: &PublicNode) -> Vec<Cid> {
+        match node {
+            // Directories decoded from the store link to their entries by CID.
+            PublicNode::Dir(dir) => dir
+                .userland
+                .values()
+                .filter_map(|link| link.get_cid().copied())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Gets the root CID of the content of a file.
+    pub(super) fn content(node: &PublicNode) -> Option<Cid> {
+        match node {
+            PublicNode::File(file) => Some(file.userland),
+            _ => None,
+        }
+    }
+
+    /// Loads every node reachable from a root through entries and `previous` links.
+    pub(super) async fn load_nodes<B: BlockStore>(
+        root: Cid,
+        store: &B,
+    ) -> Result<BTreeMap<Cid, PublicNode>> {
+        let mut nodes = BTreeMap::new();
+        let mut pending = vec![root];
+
+        while let Some(cid) = pending.pop() {
+            if nodes.contains_key(&cid) {
+                continue;
+            }
+
+            let node = store.get_deserializable::<PublicNode>(&cid).await?;
+            pending.extend(node.get_previous());
+            pending.extend(entries(&node));
+            nodes.insert(cid, node);
+        }
+
+        Ok(nodes)
+    }
+
+    /// Gets the revisions of a root directory with the time they were modified, breadth-first from the root.
+    pub(super) fn revisions(
+        root: Cid,
+        nodes: &BTreeMap<Cid, PublicNode>,
+    ) -> Vec<(Cid, DateTime<Utc>)> {
+        let mut revisions = vec![];
+        let mut visited = BTreeSet::from([root]);
+        let mut pending = VecDeque::from([root]);
+
+        while let Some(cid) = pending.pop_front() {
+            let node = &nodes[&cid];
+            revisions.push((cid, node.get_metadata().get_modified()));
+
+            for previous in node.get_previous() {
+                if visited.insert(*previous) {
+                    pending.push_back(*previous);
+                }
+            }
+        }
+
+        revisions
+    }
+
+    /// Gets the given nodes and every node below them.
+    pub(super) fn tree_nodes(
+        roots: &BTreeSet<Cid>,
+        nodes: &BTreeMap<Cid, PublicNode>,
+    ) -> BTreeSet<Cid> {
+        let mut found = BTreeSet::new();
+        let mut pending = roots.iter().copied().collect::<Vec<_>>();
+
+        while let Some(cid) = pending.pop() {
+            if found.insert(cid) {
+                pending.extend(entries(&nodes[&cid]));
+            }
+        }
+
+        found
+    }
+
+    /// Orders the nodes reachable from a root so that each node comes after every node it links to.
+    pub(super) fn post_order(root: Cid, nodes: &BTreeMap<Cid, PublicNode>) -> Vec<Cid> {
+        let mut order = vec![];
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![(root, false)];
+
+        while let Some((cid, expanded)) = pending.pop() {
+            if expanded {
+                order.push(cid);
+                continue;
+            }
+
+            if !visited.insert(cid) {
+                continue;
+            }
+
+            let node = &nodes[&cid];
+            pending.push((cid, true));
+            pending.extend(
+                node.get_previous()
+                    .iter()
+                    .copied()
+                    .chain(entries(node))
+                    .filter(|cid| !visited.contains(cid))
+                    .map(|cid| (cid, false)),
+            );
+        }
+
+        order
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod compaction_tests {
+    use chrono::{Duration, TimeZone};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{public::content, MemoryBlockStore};
+
+    /// Creates a revision on each of three days, plus a second one on the last day. Returns every revision,
+    /// oldest first.
+    async fn revisions(
+        time: DateTime<Utc>,
+        store: &mut MemoryBlockStore,
+    ) -> Vec<Rc<PublicDirectory>> {
+        let mut revisions = vec![Rc::new(PublicDirectory::new(time))];
+        let changes = [
+            (Duration::days(1), "monday.txt"),
+            (Duration::days(2), "tuesday.txt"),
+            (Duration::days(3), "wednesday.txt"),
+            (Duration::days(3) + Duration::hours(6), "wednesday.txt"),
+        ];
+
+        for (offset, name) in changes {
+            let time = time + offset;
+            let base = Rc::clone(revisions.last().unwrap());
+
+            let root_dir = Rc::clone(&base)
+                .write_bytes(
+                    &["notes".into(), name.into()],
+                    &offset.num_hours().to_le_bytes(),
+                    time,
+                    store,
+                )
+                .await
+                .unwrap()
+                .root_dir;
+
+            let root_dir = root_dir
+                .base_history_on(base, store)
+                .await
+                .unwrap()
+                .root_dir;
+
+            revisions.push(root_dir);
+        }
+
+        revisions
+    }
+
+    async fn history_len(root_dir: &Rc<PublicDirectory>, store: &MemoryBlockStore) -> usize {
+        Rc::clone(root_dir)
+            .get_history(store)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .len()
+    }
+
+    #[async_std::test]
+    async fn compaction_rewrites_previous_links_of_kept_revisions() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let revisions = revisions(time, &mut store).await;
+        let head = Rc::clone(&revisions[4]);
+
+        let OpResult { root_dir, result } = Rc::clone(&head)
+            .compact_history(&RetentionPolicy::KeepLast(2), &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(history_len(&root_dir, &store).await, 1);
+        assert_eq!(root_dir.metadata.get_size(), Some(24));
+        assert_eq!(root_dir.metadata.get_descendants(), Some(4));
+        for revision in &revisions {
+            assert!(result.contains(&revision.store(&mut store).await.unwrap()));
+        }
+
+        let previous = Rc::clone(&root_dir).at_revision(1, &store).await.unwrap();
+        assert_eq!(previous.get_modified(), time + Duration::days(3));
+
+        // The notes directory of the kept revisions only points at nodes that are still reachable.
+        let notes = Rc::clone(&root_dir)
+            .get_node(&["notes".into()], &store)
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+
+        let notes_previous = notes.get_previous().iter().next().unwrap();
+        assert!(!result.contains(notes_previous));
+
+        let OpResult {
+            root_dir: recompacted,
+            result,
+        } = Rc::clone(&root_dir)
+            .compact_history(&RetentionPolicy::KeepLast(2), &mut store)
+            .await
+            .unwrap();
+
+        assert!(result.is_empty());
+        assert_eq!(recompacted, root_dir);
+    }
+
+    #[async_std::test]
+    async fn compaction_policies_pick_revisions_by_time() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let head = Rc::clone(&revisions(time, &mut store).await[4]);
+
+        let OpResult { root_dir, .. } = Rc::clone(&head)
+            .compact_history(&RetentionPolicy::DailyFor(2), &mut store)
+            .await
+            .unwrap();
+
+        let previous = Rc::clone(&root_dir).at_revision(1, &store).await.unwrap();
+        assert_eq!(history_len(&root_dir, &store).await, 1);
+        assert_eq!(previous.get_modified(), time + Duration::days(2));
+
+        let OpResult { root_dir, .. } = Rc::clone(&head)
+            .compact_history(
+                &RetentionPolicy::DropBefore(time + Duration::days(2)),
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(history_len(&root_dir, &store).await, 2);
+
+        let OpResult { root_dir, result } = head
+            .compact_history(&RetentionPolicy::KeepLast(10), &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(history_len(&root_dir, &store).await, 4);
+        assert!(result.is_empty());
+    }
+
+    #[async_std::test]
+    async fn compaction_policies_use_times_of_operations() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let content = content::put_bytes(b"draft", &mut store).await.unwrap();
+
+        let base = Rc::new(PublicDirectory::new(time));
+        let root_dir = Rc::clone(&base)
+            .mkdir(&["drafts".into()], time + Duration::days(1), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let first = root_dir
+            .base_history_on(base, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let path = ["drafts".into(), "post.md".into()];
+        let root_dir = Rc::clone(&first)
+            .write(
+                &path,
+                content,
+                time + Duration::days(1) + Duration::hours(6),
+                &store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let second = root_dir
+            .base_history_on(first, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let root_dir = Rc::clone(&second)
+            .rm_at(&path, time + Duration::days(2), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let head = root_dir
+            .base_history_on(second, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let OpResult { root_dir, .. } = Rc::clone(&head)
+            .compact_history(&RetentionPolicy::DailyFor(2), &mut store)
+            .await
+            .unwrap();
+
+        let previous = Rc::clone(&root_dir).at_revision(1, &store).await.unwrap();
+        assert_eq!(history_len(&root_dir, &store).await, 1);
+        assert_eq!(
+            previous.get_modified(),
+            time + Duration::days(1) + Duration::hours(6)
+        );
+
+        let OpResult { root_dir, .. } = head
+            .compact_history(
+                &RetentionPolicy::DropBefore(time + Duration::days(1)),
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(history_len(&root_dir, &store).await, 2);
+    }
+}
@@ -0,0 +1,496 @@
+//! Public file content chunking.
+//!
+//! File content is split into raw blocks of [`CONTENT_CHUNK_SIZE`] bytes which are then arranged in a
+//! balanced DAG with at most [`CONTENT_LINKS_PER_NODE`] links per intermediate node. Because the layout
+//! only depends on the bytes, writing the same content always produces the same root CID.
+
+use anyhow::Result;
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::{pin_mut, AsyncRead, AsyncReadExt, Stream, StreamExt};
+use libipld::{Cid, IpldCodec};
+use serde::{Deserialize, Serialize};
+
+use crate::{BlockStore, CONTENT_CHUNK_SIZE, CONTENT_LINKS_PER_NODE};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A link to a block of file content along with the number of content bytes under it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentLink {
+    pub(crate) cid: Cid,
+    pub(crate) size: u64,
+}
+
+/// An intermediate node in the DAG of a file's content.
+///
+/// Leaves of the DAG are raw blocks, so only intermediate nodes are encoded as `ContentNode`s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentNode {
+    pub(crate) size: u64,
+    pub(crate) links: Vec<ContentLink>,
+}
+
+/// Builds a balanced content DAG from chunks that are pushed in order.
+///
+/// Blocks are put in the store as soon as they are complete, so only one pending row of links per level
+/// of the DAG is kept in memory.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content::{self, ContentBuilder}, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///     let mut builder = ContentBuilder::new();
+///
+///     builder.push_chunk(b"hello ".to_vec(), store).await.unwrap();
+///     builder.push_chunk(b"world".to_vec(), store).await.unwrap();
+///
+///     let cid = builder.finish(store).await.unwrap();
+///     let bytes = content::get_bytes(&cid, store).await.unwrap();
+///
+///     assert_eq!(bytes, b"hello world");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContentBuilder {
+    levels: Vec<Vec<ContentLink>>,
+    links_per_node: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl ContentLink {
+    /// Gets the CID of the linked block.
+    pub fn get_cid(&self) -> &Cid {
+        &self.cid
+    }
+
+    /// Gets the number of content bytes under the linked block.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl ContentNode {
+    /// Gets the links to the children of the node.
+    pub fn get_links(&self) -> &[ContentLink] {
+        &self.links
+    }
+
+    /// Gets the number of content bytes under the node.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl ContentBuilder {
+    /// Creates a new builder that uses the default DAG layout.
+    pub fn new() -> Self {
+        Self::with_links_per_node(CONTENT_LINKS_PER_NODE)
+    }
+
+    /// Creates a new builder with a custom number of links per intermediate node.
+    pub(crate) fn with_links_per_node(links_per_node: usize) -> Self {
+        Self {
+            levels: Vec::new(),
+            links_per_node: links_per_node.max(2),
+        }
+    }
+
+    /// Puts the chunk in the store as a raw block and appends it to the DAG.
+    pub async fn push_chunk<B: BlockStore>(&mut self, chunk: Vec<u8>, store: &mut B) -> Result<()> {
+        let size = chunk.len() as u64;
+        let cid = store.put_block(chunk, IpldCodec::Raw).await?;
+        self.push_link(0, ContentLink { cid, size }, store).await
+    }
+
+    /// Stores the remaining intermediate nodes and returns the CID of the root of the DAG.
+    ///
+    /// Content that fits in a single chunk is represented by the raw block itself.
+    pub async fn finish<B: BlockStore>(mut self, store: &mut B) -> Result<Cid> {
+        let mut level = 0;
+        while level < self.levels.len() {
+            let links = std::mem::take(&mut self.levels[level]);
+            let is_top = level + 1 == self.levels.len();
+            match links.len() {
+                0 => (),
+                1 if is_top => return Ok(links[0].cid),
+                _ => {
+                    let link = Self::store_node(links, store).await?;
+                    self.push_link(level + 1, link, store).await?;
+                }
+            }
+
+            level += 1;
+        }
+
+        // No chunk was pushed so the content is empty.
+        store.put_block(Vec::new(), IpldCodec::Raw).await
+    }
+
+    /// Appends a link at the given level, storing full rows of links as intermediate nodes.
+    async fn push_link<B: BlockStore>(
+        &mut self,
+        mut level: usize,
+        mut link: ContentLink,
+        store: &mut B,
+    ) -> Result<()> {
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(Vec::with_capacity(self.links_per_node));
+            }
+
+            self.levels[level].push(link);
+            if self.levels[level].len() < self.links_per_node {
+                return Ok(());
+            }
+
+            let links = std::mem::take(&mut self.levels[level]);
+            link = Self::store_node(links, store).await?;
+            level += 1;
+        }
+    }
+
+    /// Stores an intermediate node holding the given links.
+    async fn store_node<B: BlockStore>(
+        links: Vec<ContentLink>,
+        store: &mut B,
+    ) -> Result<ContentLink> {
+        let size = links.iter().map(|link| link.size).sum();
+        let cid = store.put_serializable(&ContentNode { size, links }).await?;
+        Ok(ContentLink { cid, size })
+    }
+}
+
+impl Default for ContentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Splits the bytes into chunks and stores them as a balanced DAG, returning the CID of its root.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///
+///     let first = content::put_bytes(b"hello world", store).await.unwrap();
+///     let second = content::put_bytes(b"hello world", store).await.unwrap();
+///
+///     assert_eq!(first, second);
+/// }
+/// ```
+pub async fn put_bytes<B: BlockStore>(bytes: &[u8], store: &mut B) -> Result<Cid> {
+    let mut builder = ContentBuilder::new();
+    for chunk in bytes.chunks(CONTENT_CHUNK_SIZE) {
+        builder.push_chunk(chunk.to_vec(), store).await?;
+    }
+
+    builder.finish(store).await
+}
+
+/// Reads the source to the end, storing each chunk as soon as it is complete, and returns the CID of the root of
+/// the resulting DAG.
+///
+/// At most one chunk of the source is buffered at a time. The result is the same as calling `put_bytes` with all
+/// of the bytes the source yields.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///
+///     let streamed = content::put_reader(&b"hello world"[..], store).await.unwrap();
+///     let buffered = content::put_bytes(b"hello world", store).await.unwrap();
+///
+///     assert_eq!(streamed, buffered);
+/// }
+/// ```
+pub async fn put_reader<R: AsyncRead + Unpin, B: BlockStore>(
+    mut reader: R,
+    store: &mut B,
+) -> Result<Cid> {
+    let mut builder = ContentBuilder::new();
+    loop {
+        let mut chunk = Vec::with_capacity(CONTENT_CHUNK_SIZE);
+        (&mut reader)
+            .take(CONTENT_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .await?;
+
+        let is_last = chunk.len() < CONTENT_CHUNK_SIZE;
+        if !chunk.is_empty() {
+            builder.push_chunk(chunk, store).await?;
+        }
+
+        if is_last {
+            break;
+        }
+    }
+
+    builder.finish(store).await
+}
+
+/// Reassembles the content stored in the DAG with the given root.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///
+///     let cid = content::put_bytes(b"hello world", store).await.unwrap();
+///     let bytes = content::get_bytes(&cid, store).await.unwrap();
+///
+///     assert_eq!(bytes, b"hello world");
+/// }
+/// ```
+pub async fn get_bytes<B: BlockStore>(root: &Cid, store: &B) -> Result<Vec<u8>> {
+    get_range(root, 0, u64::MAX, store).await
+}
+
+/// Reads up to `len` bytes starting at `offset` from the content stored in the DAG with the given root.
+///
+/// Only the blocks that cover the requested range are fetched from the store.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///
+///     let cid = content::put_bytes(b"hello world", store).await.unwrap();
+///     let bytes = content::get_range(&cid, 6, 5, store).await.unwrap();
+///
+///     assert_eq!(bytes, b"world");
+/// }
+/// ```
+pub async fn get_range<B: BlockStore>(
+    root: &Cid,
+    offset: u64,
+    len: u64,
+    store: &B,
+) -> Result<Vec<u8>> {
+    let stream = stream_range(*root, offset, len, store);
+    pin_mut!(stream);
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        bytes.extend_from_slice(&chunk?);
+    }
+
+    Ok(bytes)
+}
+
+/// Gets a stream of the chunks covering up to `len` bytes starting at `offset` of the content stored in the DAG
+/// with the given root.
+///
+/// Blocks are fetched lazily and subtrees that lie outside of the range are never loaded.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+/// use futures_util::pin_mut;
+/// use async_std::stream::StreamExt;
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///     let cid = content::put_bytes(b"hello world", store).await.unwrap();
+///
+///     let stream = content::stream_range(cid, 0, u64::MAX, store);
+///
+///     pin_mut!(stream);
+///
+///     while let Some(chunk) = stream.next().await {
+///         println!("chunk = {:?}", chunk.unwrap());
+///     }
+/// }
+/// ```
+pub fn stream_range<B: BlockStore>(
+    root: Cid,
+    offset: u64,
+    len: u64,
+    store: &B,
+) -> impl Stream<Item = Result<Bytes>> + '_ {
+    let end = offset.saturating_add(len);
+    try_stream! {
+        // Each entry holds a block and the position of its first byte in the content.
+        let mut stack = vec![(root, 0)];
+        while let Some((cid, start)) = stack.pop() {
+            if is_raw(&cid) {
+                let block = store.get_block(&cid).await?;
+                let block_len = block.len() as u64;
+                let from = offset.saturating_sub(start).min(block_len) as usize;
+                let to = end.saturating_sub(start).min(block_len) as usize;
+
+                if from == 0 && to == block.len() && to > 0 {
+                    yield Bytes::from(block.into_owned());
+                } else if from < to {
+                    yield Bytes::copy_from_slice(&block[from..to]);
+                }
+            } else {
+                let node = store.get_deserializable::<ContentNode>(&cid).await?;
+                let mut children = Vec::new();
+                let mut child_start = start;
+                for link in node.links.iter() {
+                    let child_end = child_start + link.size;
+                    if child_end > offset && child_start < end {
+                        children.push((link.cid, child_start));
+                    }
+
+                    child_start = child_end;
+                }
+
+                stack.extend(children.into_iter().rev());
+            }
+        }
+    }
+}
+
+/// Gets the number of content bytes stored in the DAG with the given root.
+///
+/// Only the root block is fetched from the store.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::content, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &mut MemoryBlockStore::default();
+///
+///     let cid = content::put_bytes(b"hello world", store).await.unwrap();
+///     let size = content::get_size(&cid, store).await.unwrap();
+///
+///     assert_eq!(size, 11);
+/// }
+/// ```
+pub async fn get_size<B: BlockStore>(root: &Cid, store: &B) -> Result<u64> {
+    if is_raw(root) {
+        return Ok(store.get_block(root).await?.len() as u64);
+    }
+
+    let node = store.get_deserializable::<ContentNode>(root).await?;
+    Ok(node.size)
+}
+
+/// Checks if the CID points to a raw leaf block.
+pub(crate) fn is_raw(cid: &Cid) -> bool {
+    cid.codec() == u64::from(IpldCodec::Raw)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod content_tests {
+    use super::*;
+    use crate::MemoryBlockStore;
+
+    #[async_std::test]
+    async fn content_spanning_many_levels_can_be_reassembled() {
+        let store = &mut MemoryBlockStore::default();
+        let bytes = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+
+        let mut builder = ContentBuilder::with_links_per_node(3);
+        for chunk in bytes.chunks(10) {
+            builder.push_chunk(chunk.to_vec(), store).await.unwrap();
+        }
+
+        let root = builder.finish(store).await.unwrap();
+        let node = store
+            .get_deserializable::<ContentNode>(&root)
+            .await
+            .unwrap();
+
+        assert_eq!(node.size, 1000);
+        assert!(node.links.len() <= 3);
+        assert_eq!(get_size(&root, store).await.unwrap(), 1000);
+        assert_eq!(get_bytes(&root, store).await.unwrap(), bytes);
+    }
+
+    #[async_std::test]
+    async fn range_reads_only_cover_requested_bytes() {
+        let store = &mut MemoryBlockStore::default();
+        let bytes = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+
+        let mut builder = ContentBuilder::with_links_per_node(4);
+        for chunk in bytes.chunks(7) {
+            builder.push_chunk(chunk.to_vec(), store).await.unwrap();
+        }
+
+        let root = builder.finish(store).await.unwrap();
+
+        for (offset, len) in [
+            (0, 1000),
+            (3, 10),
+            (95, 200),
+            (990, 100),
+            (1000, 5),
+            (512, 0),
+        ] {
+            let range = get_range(&root, offset, len, store).await.unwrap();
+            let start = (offset as usize).min(bytes.len());
+            let end = (offset as usize + len as usize).min(bytes.len());
+            assert_eq!(range, &bytes[start..end]);
+        }
+    }
+
+    #[async_std::test]
+    async fn put_reader_and_put_bytes_produce_the_same_dag() {
+        let store = &mut MemoryBlockStore::default();
+
+        for len in [0, 10, CONTENT_CHUNK_SIZE, 2 * CONTENT_CHUNK_SIZE + 1] {
+            let bytes = (0..=255).cycle().take(len).collect::<Vec<u8>>();
+
+            let streamed = put_reader(&bytes[..], store).await.unwrap();
+            let buffered = put_bytes(&bytes, store).await.unwrap();
+
+            assert_eq!(streamed, buffered);
+        }
+    }
+
+    #[async_std::test]
+    async fn small_and_empty_content_is_stored_as_a_single_raw_block() {
+        let store = &mut MemoryBlockStore::default();
+
+        let small = put_bytes(b"tiny", store).await.unwrap();
+        let empty = put_bytes(b"", store).await.unwrap();
+
+        assert!(is_raw(&small));
+        assert!(is_raw(&empty));
+        assert_eq!(get_bytes(&small, store).await.unwrap(), b"tiny");
+        assert!(get_bytes(&empty, store).await.unwrap().is_empty());
+    }
+}
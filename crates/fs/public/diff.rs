@@ -0,0 +1,272 @@
+//! Structural differences between public trees.
+
+use std::{collections::BTreeSet, rc::Rc};
+
+use anyhow::Result;
+
+use crate::BlockStore;
+
+use super::{PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The kinds of change [`PublicDirectory::diff`] reports for a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    /// The path only exists in the new tree.
+    Added,
+    /// The path only exists in the old tree.
+    Removed,
+    /// The path has a node of the same kind in both trees but its content or metadata changed.
+    Modified,
+    /// The path is a file, directory or symlink in one tree and a different kind of node in the other.
+    TypeChanged,
+}
+
+/// A path that differs between two trees.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Change {
+    /// The path that changed.
+    pub path: Vec<String>,
+    /// How the path changed.
+    pub kind: ChangeKind,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicDirectory {
+    /// Gets the paths that differ between this tree and a newer tree, sorted by path.
+    ///
+    /// Entries are compared by CID, so a subtree is skipped without being loaded when its CID is the same in
+    /// both trees. Only the top of an added or removed subtree is reported. A directory that exists in both
+    /// trees is reported as modified if its own metadata changed, not just because something below it did. The
+    /// modified times of directories are left out, since every change moves them forward.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{Change, ChangeKind, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let old_root = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir: new_root, .. } = Rc::clone(&old_root)
+    ///         .mkdir(&["pictures".into(), "cats".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let changes = old_root.diff(new_root, &mut store).await.unwrap();
+    ///
+    ///     assert_eq!(
+    ///         changes,
+    ///         [Change { path: vec!["pictures".into()], kind: ChangeKind::Added }]
+    ///     );
+    /// }
+    /// ```
+    pub async fn diff<B: BlockStore>(
+        self: Rc<Self>,
+        other: Rc<Self>,
+        store: &mut B,
+    ) -> Result<Vec<Change>> {
+        let mut changes = vec![];
+        if !utils::same_own_metadata(&self, &other) {
+            changes.push(Change {
+                path: vec![],
+                kind: ChangeKind::Modified,
+            });
+        }
+
+        let mut pending = vec![(vec![], self, other)];
+        while let Some((path, old_dir, new_dir)) = pending.pop() {
+            let names = old_dir
+                .userland
+                .keys()
+                .chain(new_dir.userland.keys())
+                .collect::<BTreeSet<_>>();
+
+            for name in names {
+                let mut path = path.clone();
+                path.push(name.clone());
+
+                let (old_link, new_link) =
+                    match (old_dir.userland.get(name), new_dir.userland.get(name)) {
+                        (Some(old_link), Some(new_link)) => (old_link, new_link),
+                        (Some(_), None) => {
+                            changes.push(Change {
+                                path,
+                                kind: ChangeKind::Removed,
+                            });
+                            continue;
+                        }
+                        (None, _) => {
+                            changes.push(Change {
+                                path,
+                                kind: ChangeKind::Added,
+                            });
+                            continue;
+                        }
+                    };
+
+                if old_link.deep_eq(new_link, store).await? {
+                    continue;
+                }
+
+                let kind = match (
+                    old_link.resolve_value(store).await?,
+                    new_link.resolve_value(store).await?,
+                ) {
+                    (PublicNode::Dir(old_dir), PublicNode::Dir(new_dir)) => {
+                        let modified = !utils::same_own_metadata(old_dir, new_dir);
+                        pending.push((path.clone(), Rc::clone(old_dir), Rc::clone(new_dir)));
+
+                        if !modified {
+                            continue;
+                        }
+
+                        ChangeKind::Modified
+                    }
+                    (PublicNode::File(_), PublicNode::File(_))
+                    | (PublicNode::Symlink(_), PublicNode::Symlink(_)) => ChangeKind::Modified,
+                    _ => ChangeKind::TypeChanged,
+                };
+
+                changes.push(Change { path, kind });
+            }
+        }
+
+        changes.sort();
+        Ok(changes)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use super::PublicDirectory;
+
+    /// Checks if two directories have the same metadata, apart from the sizes and the modified time that change
+    /// along with their children.
+    pub(super) fn same_own_metadata(old_dir: &PublicDirectory, new_dir: &PublicDirectory) -> bool {
+        let (old, new) = (&old_dir.metadata, &new_dir.metadata);
+
+        old.get_created() == new.get_created()
+            && old.unix_fs.mode == new.unix_fs.mode
+            && old.unix_fs.kind == new.unix_fs.kind
+            && old.xattrs == new.xattrs
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod diff_tests {
+    use chrono::Utc;
+    use libipld::Ipld;
+
+    use super::*;
+    use crate::MemoryBlockStore;
+
+    fn change(path: &[&str], kind: ChangeKind) -> Change {
+        Change {
+            path: path.iter().map(|segment| segment.to_string()).collect(),
+            kind,
+        }
+    }
+
+    #[async_std::test]
+    async fn diff_reports_each_kind_of_change() {
+        let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
+
+        let mut old_root = Rc::new(PublicDirectory::new(time));
+        for path in [["docs", "a.txt"], ["docs", "b.txt"], ["misc", "c.txt"]] {
+            let path = path.map(String::from);
+            old_root = old_root
+                .write_bytes(&path, b"old", time, &mut store)
+                .await
+                .unwrap()
+                .root_dir;
+        }
+
+        let old_root = old_root
+            .mkdir(&["later".into()], time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = Rc::clone(&old_root)
+            .write_bytes(&["docs".into(), "a.txt".into()], b"new", time, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = new_root
+            .rm(&["later".into()], &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = new_root
+            .write_bytes(&["later".into()], b"now a file", time, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = new_root
+            .rm(&["misc".into(), "c.txt".into()], &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = new_root
+            .symlink("docs/b.txt", &["b".into()], time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let new_root = new_root
+            .set_xattr(
+                &["misc".into()],
+                "color",
+                Ipld::String("red".into()),
+                &store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let changes = Rc::clone(&old_root)
+            .diff(Rc::clone(&new_root), &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            changes,
+            [
+                change(&["b"], ChangeKind::Added),
+                change(&["docs", "a.txt"], ChangeKind::Modified),
+                change(&["later"], ChangeKind::TypeChanged),
+                change(&["misc"], ChangeKind::Modified),
+                change(&["misc", "c.txt"], ChangeKind::Removed),
+            ]
+        );
+
+        assert!(Rc::clone(&new_root)
+            .diff(new_root, &mut store)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
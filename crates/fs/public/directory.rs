@@ -1,18 +1,25 @@
 //! Public fs directory node.
 
-use std::{collections::BTreeMap, rc::Rc};
-
-use crate::{error, AsyncSerialize, BlockStore, FsError, Id, Metadata, UnixFsNodeKind};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    error, validate_segments, AsyncSerialize, BlockStore, FsError, Id, Metadata, UnixFsMode,
+    UnixFsNodeKind, SYMLINK_HOP_LIMIT,
+};
 use anyhow::{bail, ensure, Result};
 use async_recursion::async_recursion;
 use async_stream::try_stream;
 use async_trait::async_trait;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use futures::Stream;
+use futures::{pin_mut, AsyncRead, Stream, StreamExt};
 use libipld::Cid;
 use serde::{ser::Error as SerError, Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{PublicFile, PublicLink, PublicNode};
+use super::{content, PublicFile, PublicLink, PublicNode, PublicSymlink, Usage};
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
@@ -30,18 +37,23 @@ use super::{PublicFile, PublicLink, PublicNode};
 ///
 /// println!("id = {}", dir.get_id());
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct PublicDirectory {
     pub(crate) metadata: Metadata,
     pub(crate) userland: BTreeMap<String, PublicLink>,
-    pub(crate) previous: Option<Cid>,
+    pub(crate) previous: BTreeSet<Cid>,
+    pub(crate) journal: Option<Cid>,
+    pub(crate) symlink_hop_limit: usize,
 }
 
 #[derive(Serialize, Deserialize)]
 struct PublicDirectorySerde {
     metadata: Metadata,
     userland: BTreeMap<String, Cid>,
-    previous: Option<Cid>,
+    #[serde(deserialize_with = "super::previous::deserialize")]
+    previous: BTreeSet<Cid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    journal: Option<Cid>,
 }
 
 /// The result of an operation applied to a directory.
@@ -53,6 +65,18 @@ pub struct OpResult<T> {
     pub result: T,
 }
 
+/// How `PublicDirectory::mv` handles a destination that already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveMode {
+    /// Fails with `FsError::FileAlreadyExists`.
+    NoClobber,
+    /// Replaces the destination. A directory can only replace an empty directory and other nodes can't replace
+    /// a directory.
+    Overwrite,
+    /// Swaps the source and the destination, which has to exist.
+    Exchange,
+}
+
 /// Represents the directory nodes along a path.
 ///
 /// # Examples
@@ -139,6 +163,8 @@ impl PathNodes {
 
     /// Constructs a diverged path nodes by fixing up links in a `PathNodes` and returning the resulting root node.
     ///
+    /// The sizes of each ancestor are updated with how much the directory below it changed.
+    ///
     /// # Examples
     ///
     /// ```
@@ -157,21 +183,58 @@ impl PathNodes {
     /// println!("new_root = {:?}", new_root);
     /// ```
     pub fn reconstruct(self) -> Rc<PublicDirectory> {
-        if self.path.is_empty() {
-            return self.tail;
-        }
-
         let mut working_dir = self.tail;
         for (dir, segment) in self.path.iter().rev() {
             let mut dir = (**dir).clone();
             let link = PublicLink::with_dir(working_dir);
+            let old_usage = utils::cached_usage(dir.userland.get(segment));
+            dir.add_usage(old_usage, utils::cached_usage(Some(&link)));
             dir.userland.insert(segment.clone(), link);
             working_dir = Rc::new(dir);
         }
 
+        // The journal of the old root doesn't describe the change.
+        if working_dir.journal.is_some() {
+            Rc::make_mut(&mut working_dir).journal = None;
+        }
+
         working_dir
     }
 
+    /// Constructs a diverged path nodes like [`PathNodes::reconstruct`] for a change made at `time`.
+    ///
+    /// The tail and every directory along the path get `time` as their modified time, so the modified time of
+    /// the resulting root is the time of the latest change in its tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::public::{PublicDirectory, PathNodes, PublicNode};
+    /// use std::rc::Rc;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let time = Utc.timestamp(1_650_000_000, 0);
+    /// let nodes = PathNodes::new(
+    ///     Utc::now(),
+    ///     &["movies".into(), "anime".into()],
+    ///     Rc::new(PublicDirectory::new(Utc::now())),
+    /// );
+    ///
+    /// let new_root = nodes.reconstruct_at(time);
+    ///
+    /// let new_root = PublicNode::Dir(new_root);
+    ///
+    /// assert_eq!(new_root.get_metadata().get_modified(), time);
+    /// ```
+    pub fn reconstruct_at(mut self, time: DateTime<Utc>) -> Rc<PublicDirectory> {
+        Rc::make_mut(&mut self.tail).metadata.set_modified(time);
+        for (dir, _) in self.path.iter_mut() {
+            Rc::make_mut(dir).metadata.set_modified(time);
+        }
+
+        self.reconstruct()
+    }
+
     /// Returns the length of the path nodes.
     ///
     /// # Examples
@@ -232,40 +295,241 @@ impl PublicDirectory {
         Self {
             metadata: Metadata::new(time, UnixFsNodeKind::Dir),
             userland: BTreeMap::new(),
-            previous: None,
+            previous: BTreeSet::new(),
+            journal: None,
+            symlink_hop_limit: SYMLINK_HOP_LIMIT,
+        }
+    }
+
+    /// Sets how many symlinks operations on paths from this directory follow before failing with
+    /// [`FsError::SymlinkLoop`]. It defaults to [`SYMLINK_HOP_LIMIT`].
+    ///
+    /// The limit is a runtime setting, not part of the tree. It isn't stored, so directories loaded from a
+    /// block store use the default and the limit has to be set again after loading a root. It is also ignored
+    /// when comparing directories. Roots returned by operations keep the limit of the directory the operation
+    /// was called on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, FsError, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } =
+    ///         Rc::new(PublicDirectory::new(Utc::now()).with_symlink_hop_limit(1))
+    ///             .mkdir(&["pictures".into()], Utc::now(), &store)
+    ///             .await
+    ///             .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .symlink("pictures", &["images".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .symlink("images", &["photos".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(Rc::clone(&root_dir).ls(&["images".into()], &store).await.is_ok());
+    ///
+    ///     let error = root_dir.ls(&["photos".into()], &store).await.unwrap_err();
+    ///
+    ///     assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::SymlinkLoop));
+    /// }
+    /// ```
+    pub fn with_symlink_hop_limit(mut self, hop_limit: usize) -> Self {
+        self.symlink_hop_limit = hop_limit;
+        self
+    }
+
+    /// Gets how many symlinks operations on paths from this directory follow.
+    pub fn get_symlink_hop_limit(&self) -> usize {
+        self.symlink_hop_limit
+    }
+
+    /// Gets the CIDs of the revisions this directory was derived from.
+    ///
+    /// A directory usually has one previous revision, or two if it is the result of a merge.
+    pub fn get_previous(self: &Rc<Self>) -> &BTreeSet<Cid> {
+        &self.previous
+    }
+
+    /// Sets or removes an entry and updates the sizes of the directory. Returns the entry it replaced.
+    pub(crate) async fn replace_entry<B: BlockStore>(
+        &mut self,
+        name: &str,
+        link: Option<PublicLink>,
+        store: &B,
+    ) -> Result<Option<PublicLink>> {
+        let old_usage = match self.userland.get(name) {
+            Some(old_link) => old_link.resolve_value(store).await?.get_usage(),
+            None => Some(Usage::default()),
+        };
+
+        let new_usage = match &link {
+            Some(link) => link.resolve_value(store).await?.get_usage(),
+            None => Some(Usage::default()),
+        };
+
+        self.add_usage(old_usage, new_usage);
+
+        Ok(match link {
+            Some(link) => self.userland.insert(name.to_string(), link),
+            None => self.userland.remove(name),
+        })
+    }
+
+    /// Adds up the sizes of this directory and the directories below it that haven't been added up yet.
+    ///
+    /// Sizes haven't been added up for directories decoded from encodings without them, or after an entry
+    /// that wasn't loaded was replaced. Directories whose sizes are added up, including those with files of
+    /// unknown size below them, are returned as they are without loading their entries.
+    #[async_recursion(?Send)]
+    pub(crate) async fn complete_usage<B>(self: Rc<Self>, store: &B) -> Result<Rc<Self>>
+    where
+        B: BlockStore,
+    {
+        if self.get_totals().is_some() {
+            return Ok(self);
+        }
+
+        let mut directory = (*self).clone();
+        let mut totals = Usage::default();
+        for (name, link) in self.userland.iter() {
+            let usage = match link.resolve_value(store).await? {
+                PublicNode::Dir(dir) => {
+                    let completed = Rc::clone(dir).complete_usage(store).await?;
+                    if !Rc::ptr_eq(dir, &completed) {
+                        let link = PublicLink::with_dir(Rc::clone(&completed));
+                        directory.userland.insert(name.clone(), link);
+                    }
+
+                    PublicNode::Dir(completed).get_usage()
+                }
+                node => node.get_usage(),
+            };
+
+            totals = totals.add(usage.unwrap_or_default());
         }
+
+        directory.set_totals(Some(totals));
+        Ok(Rc::new(directory))
+    }
+
+    /// Gets the sizes of the entries of this directory added up, or `None` if they haven't been.
+    pub(crate) fn get_totals(&self) -> Option<Usage> {
+        Some(Usage {
+            size: self.metadata.size?,
+            nodes: self.metadata.descendants?,
+            unknown_sizes: self.metadata.unknown_sizes,
+        })
     }
 
-    /// Gets the previous value of the directory.
-    pub fn get_previous(self: &Rc<Self>) -> Option<Cid> {
-        self.previous
+    /// Sets the sizes of the entries of this directory added up.
+    fn set_totals(&mut self, totals: Option<Usage>) {
+        let metadata = &mut self.metadata;
+        metadata.size = totals.map(|totals| totals.size);
+        metadata.descendants = totals.map(|totals| totals.nodes);
+        metadata.unknown_sizes = totals.map_or(0, |totals| totals.unknown_sizes);
+    }
+
+    /// Replaces what an entry adds to the sizes of the directory. The sizes are left to be added up again if
+    /// either usage is `None`.
+    fn add_usage(&mut self, old_usage: Option<Usage>, new_usage: Option<Usage>) {
+        let totals = match (self.get_totals(), old_usage, new_usage) {
+            (Some(totals), Some(old_usage), Some(new_usage)) => {
+                totals.replace(old_usage, new_usage)
+            }
+            _ => None,
+        };
+
+        self.set_totals(totals);
     }
 
     /// Gets the directory nodes along specified path.
     ///
-    /// Supports cases where the entire path does not exist.
+    /// Supports cases where the entire path does not exist. Symlinks along the path are followed.
     pub(crate) async fn get_path_nodes<B: BlockStore>(
         self: Rc<Self>,
         path_segments: &[String],
         store: &B,
     ) -> Result<PathNodesResult> {
+        let hop_limit = self.symlink_hop_limit;
+        let (result, _) = self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the directory nodes along specified path, following at most `hop_limit` symlinks.
+    ///
+    /// Relative symlink targets are resolved from the directory containing the link and absolute ones from
+    /// this directory. Along with the result, it returns the segments that come after the one resolution
+    /// stopped at. Fails with [`FsError::InvalidPathSegment`] if a segment isn't a valid name.
+    async fn resolve_path_nodes<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        hop_limit: usize,
+        store: &B,
+    ) -> Result<(PathNodesResult, Vec<utils::PathSegment>)> {
+        use utils::PathSegment::*;
         use PathNodesResult::*;
+        validate_segments(path_segments)?;
+
+        let root_dir = Rc::clone(&self);
         let mut working_node = self;
         let mut path_nodes = Vec::with_capacity(path_segments.len());
+        let mut pending = path_segments
+            .iter()
+            .map(|segment| Name(segment.clone()))
+            .collect::<VecDeque<_>>();
+        let mut hops = 0;
+
+        while let Some(segment) = pending.pop_front() {
+            let segment = match segment {
+                Name(segment) => segment,
+                Parent => {
+                    if let Some((parent, _)) = path_nodes.pop() {
+                        working_node = parent;
+                    }
+
+                    continue;
+                }
+            };
 
-        for segment in path_segments.iter() {
-            match working_node.lookup_node(segment, store).await? {
+            match working_node.lookup_node(&segment, store).await? {
                 Some(PublicNode::Dir(ref directory)) => {
-                    path_nodes.push((Rc::clone(&working_node), segment.clone()));
+                    path_nodes.push((Rc::clone(&working_node), segment));
                     working_node = Rc::clone(directory);
                 }
+                Some(PublicNode::Symlink(ref symlink)) => {
+                    hops += 1;
+                    ensure!(hops <= hop_limit, FsError::SymlinkLoop);
+
+                    let (absolute, target) = utils::parse_symlink_target(&symlink.target);
+                    if absolute {
+                        path_nodes.clear();
+                        working_node = Rc::clone(&root_dir);
+                    }
+
+                    for segment in target.into_iter().rev() {
+                        pending.push_front(segment);
+                    }
+                }
                 Some(_) => {
                     let path_nodes = PathNodes {
                         path: path_nodes,
                         tail: Rc::clone(&working_node),
                     };
 
-                    return Ok(NotADirectory(path_nodes, segment.clone()));
+                    return Ok((NotADirectory(path_nodes, segment), pending.into()));
                 }
                 None => {
                     let path_nodes = PathNodes {
@@ -273,15 +537,18 @@ impl PublicDirectory {
                         tail: Rc::clone(&working_node),
                     };
 
-                    return Ok(MissingLink(path_nodes, segment.clone()));
+                    return Ok((MissingLink(path_nodes, segment), pending.into()));
                 }
             }
         }
 
-        Ok(Complete(PathNodes {
-            path: path_nodes,
-            tail: Rc::clone(&working_node),
-        }))
+        Ok((
+            Complete(PathNodes {
+                path: path_nodes,
+                tail: Rc::clone(&working_node),
+            }),
+            vec![],
+        ))
     }
 
     /// Gets the directory nodes along a path and also supports creating missing intermediate directories.
@@ -292,13 +559,25 @@ impl PublicDirectory {
         store: &B,
     ) -> Result<PathNodes> {
         use PathNodesResult::*;
-        match self.get_path_nodes(path_segments, store).await? {
+        let hop_limit = self.symlink_hop_limit;
+        let (result, rest) = self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?;
+
+        match result {
             Complete(path_nodes) => Ok(path_nodes),
             NotADirectory(_, _) => error(FsError::InvalidPath),
             MissingLink(path_so_far, missing_link) => {
-                let missing_path = path_segments.split_at(path_so_far.path.len() + 1).1;
+                let missing_path = rest
+                    .into_iter()
+                    .map(|segment| match segment {
+                        utils::PathSegment::Name(name) => Ok(name),
+                        utils::PathSegment::Parent => error(FsError::InvalidPath),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
                 let missing_path_nodes =
-                    PathNodes::new(time, missing_path, Rc::new(PublicDirectory::new(time)));
+                    PathNodes::new(time, &missing_path, Rc::new(PublicDirectory::new(time)));
 
                 Ok(PathNodes {
                     path: [
@@ -469,25 +748,19 @@ impl PublicDirectory {
         store: &mut B,
     ) -> Result<OpResult<Cid>> {
         let root_dir = Rc::clone(&self);
-        let (path, filename) = utils::split_last(path_segments)?;
+        let file = self.get_file(path_segments, store).await?;
 
-        match self.get_path_nodes(path, store).await? {
-            PathNodesResult::Complete(node_path) => {
-                match node_path.tail.lookup_node(filename, store).await? {
-                    Some(PublicNode::File(file)) => Ok(OpResult {
-                        root_dir,
-                        result: file.userland,
-                    }),
-                    Some(PublicNode::Dir(_)) => error(FsError::NotAFile),
-                    None => error(FsError::NotFound),
-                }
-            }
-            _ => error(FsError::NotFound),
-        }
+        Ok(OpResult {
+            root_dir,
+            result: file.userland,
+        })
     }
 
     /// Writes a file to the directory.
     ///
+    /// Symlinks are followed, including one at the end of the path, in which case the file it points to is
+    /// written. That file is created if it doesn't exist yet.
+    ///
     /// # Examples
     ///
     /// ```
@@ -523,43 +796,76 @@ impl PublicDirectory {
         let (directory_path, filename) = utils::split_last(path_segments)?;
 
         // This will create directories if they don't exist yet
-        let mut directory_path_nodes = self
+        let mut directory_path_nodes = Rc::clone(&self)
             .get_path_nodes_or_create(directory_path, time, store)
             .await?;
 
+        // Write to the file a symlink points to instead of replacing the symlink
+        let mut filename = filename.clone();
+        if let Some(PublicNode::Symlink(_)) = directory_path_nodes
+            .tail
+            .lookup_node(&filename, store)
+            .await?
+        {
+            let hop_limit = self.symlink_hop_limit;
+            (directory_path_nodes, filename) = match self
+                .resolve_path_nodes(path_segments, hop_limit, store)
+                .await?
+            {
+                (PathNodesResult::NotADirectory(path_nodes, name), rest)
+                | (PathNodesResult::MissingLink(path_nodes, name), rest)
+                    if rest.is_empty() =>
+                {
+                    (path_nodes, name)
+                }
+                (PathNodesResult::Complete(_), _) => bail!(FsError::DirectoryAlreadyExists),
+                _ => bail!(FsError::NotFound),
+            };
+        }
+
         let mut directory = (*directory_path_nodes.tail).clone();
 
         // Modify the file if it already exists, otherwise create a new file with expected content
-        let file = match directory.lookup_node(filename, store).await? {
+        let mut file = match directory.lookup_node(&filename, store).await? {
             Some(PublicNode::File(file_before)) => {
                 let mut file = (*file_before).clone();
                 file.userland = content_cid;
-                file.metadata = Metadata::new(time, UnixFsNodeKind::File);
+                file.metadata.set_modified(time);
                 file
             }
             Some(PublicNode::Dir(_)) => bail!(FsError::DirectoryAlreadyExists),
+            Some(PublicNode::Symlink(_)) => bail!(FsError::FileAlreadyExists),
             None => PublicFile::new(time, content_cid),
         };
 
+        // The size is only known if the content is in the store
+        file.metadata.size = content::get_size(&content_cid, store).await.ok();
+
         // insert the file into its parent directory
+        let link = PublicLink::with_file(Rc::new(file));
         directory
-            .userland
-            .insert(filename.to_string(), PublicLink::with_file(Rc::new(file)));
+            .replace_entry(&filename, Some(link), store)
+            .await?;
         directory_path_nodes.tail = Rc::new(directory);
 
         // reconstruct the file path
         Ok(OpResult {
-            root_dir: directory_path_nodes.reconstruct(),
+            root_dir: directory_path_nodes
+                .reconstruct_at(time)
+                .complete_usage(store)
+                .await?,
             result: (),
         })
     }
 
-    /// Creates a new directory at the specified path.
+    /// Splits the bytes into blocks, puts them in the store and writes a file pointing to them.
+    ///
+    /// The result is the CID of the root of the content DAG. Writing the same bytes always produces the same CID.
     ///
     /// # Examples
     ///
     /// ```
-    /// use wnfs::{public::{PublicDirectory, OpResult}, Id, MemoryBlockStore};
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
     /// use std::rc::Rc;
     /// use chrono::Utc;
     ///
@@ -568,244 +874,279 @@ impl PublicDirectory {
     ///     let mut store = MemoryBlockStore::default();
     ///
     ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
-    ///         .mkdir(&["pictures".into(), "cats".into()], Utc::now(), &store)
+    ///         .write_bytes(
+    ///             &["notes".into(), "hello.txt".into()],
+    ///             b"hello world",
+    ///             Utc::now(),
+    ///             &mut store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .read_bytes(&["notes".into(), "hello.txt".into()], &mut store)
     ///         .await
     ///         .unwrap();
+    ///
+    ///     assert_eq!(result, b"hello world");
     /// }
     /// ```
-    ///
-    /// This method acts like `mkdir -p` in Unix because it creates intermediate directories if they do not exist.
-    pub async fn mkdir<B: BlockStore>(
+    pub async fn write_bytes<B: BlockStore>(
         self: Rc<Self>,
         path_segments: &[String],
+        content: &[u8],
         time: DateTime<Utc>,
-        store: &B,
-    ) -> Result<OpResult<()>> {
-        let path_nodes = self
-            .get_path_nodes_or_create(path_segments, time, store)
-            .await?;
+        store: &mut B,
+    ) -> Result<OpResult<Cid>> {
+        let content_cid = content::put_bytes(content, store).await?;
+        let OpResult { root_dir, .. } = self.write(path_segments, content_cid, time, store).await?;
 
         Ok(OpResult {
-            root_dir: path_nodes.reconstruct(),
-            result: (),
+            root_dir,
+            result: content_cid,
         })
     }
 
-    /// Returns the name and metadata of the direct children of a directory.
+    /// Reads content from the source in chunks, puts them in the store as they are read and writes a file pointing
+    /// to them.
+    ///
+    /// At most one chunk of the source is buffered at a time. The resulting content CID is the same one
+    /// `write_bytes` produces for the same bytes.
     ///
     /// # Examples
     ///
     /// ```
     /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
-    /// use libipld::cid::Cid;
     /// use std::rc::Rc;
     /// use chrono::Utc;
     ///
     /// #[async_std::main]
     /// async fn main() {
-    ///     let time = Utc::now();
-    ///     let dir = Rc::new(PublicDirectory::new(time));
-    ///     let store = MemoryBlockStore::default();
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let source = &b"hello world"[..];
     ///
     ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
-    ///         .write(
-    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
-    ///             Cid::default(),
-    ///             Utc::now(),
-    ///             &store
-    ///         )
+    ///         .write_stream(&["hello.txt".into()], source, Utc::now(), &mut store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir, result } = root_dir
-    ///         .ls(&["pictures".into(), "cats".into()], &store)
+    ///     let OpResult { result, .. } = root_dir
+    ///         .read_bytes(&["hello.txt".into()], &mut store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     assert_eq!(result.len(), 1);
-    ///     assert_eq!(result[0].0, "tabby.png");
+    ///     assert_eq!(result, b"hello world");
     /// }
     /// ```
-    pub async fn ls<B: BlockStore>(
+    pub async fn write_stream<R: AsyncRead + Unpin, B: BlockStore>(
         self: Rc<Self>,
         path_segments: &[String],
-        store: &B,
-    ) -> Result<OpResult<Vec<(String, Metadata)>>> {
-        let root_dir = Rc::clone(&self);
-        match self.get_path_nodes(path_segments, store).await? {
-            PathNodesResult::Complete(path_nodes) => {
-                let mut result = vec![];
-                for (name, link) in path_nodes.tail.userland.iter() {
-                    match link.resolve_value(store).await? {
-                        PublicNode::File(file) => {
-                            result.push((name.clone(), file.metadata.clone()));
-                        }
-                        PublicNode::Dir(dir) => {
-                            result.push((name.clone(), dir.metadata.clone()));
-                        }
-                    }
-                }
-                Ok(OpResult { root_dir, result })
-            }
-            _ => bail!(FsError::NotFound),
-        }
+        source: R,
+        time: DateTime<Utc>,
+        store: &mut B,
+    ) -> Result<OpResult<Cid>> {
+        let content_cid = content::put_reader(source, store).await?;
+        let OpResult { root_dir, .. } = self.write(path_segments, content_cid, time, store).await?;
+
+        Ok(OpResult {
+            root_dir,
+            result: content_cid,
+        })
     }
 
-    /// Removes a file or directory from the directory.
+    /// Reads the content of a file written with `write_bytes` from the directory.
     ///
     /// # Examples
     ///
     /// ```
     /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
-    /// use libipld::cid::Cid;
     /// use std::rc::Rc;
     /// use chrono::Utc;
     ///
     /// #[async_std::main]
     /// async fn main() {
-    ///     let time = Utc::now();
-    ///     let dir = Rc::new(PublicDirectory::new(time));
-    ///     let store = MemoryBlockStore::default();
+    ///     let mut store = MemoryBlockStore::default();
     ///
     ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
-    ///         .write(
-    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
-    ///             Cid::default(),
-    ///             Utc::now(),
-    ///             &store
-    ///         )
-    ///         .await
-    ///         .unwrap();
-    ///
-    ///     let OpResult { root_dir, .. } = root_dir
-    ///         .rm(&["pictures".into(), "cats".into()], &store)
+    ///         .write_bytes(&["hello.txt".into()], b"hello world", Utc::now(), &mut store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir, result } = root_dir
-    ///         .ls(&["pictures".into()], &store)
+    ///     let OpResult { result, .. } = root_dir
+    ///         .read_bytes(&["hello.txt".into()], &mut store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     assert_eq!(result.len(), 0);
+    ///     assert_eq!(result, b"hello world");
     /// }
     /// ```
-    pub async fn rm<B: BlockStore>(
+    pub async fn read_bytes<B: BlockStore>(
         self: Rc<Self>,
         path_segments: &[String],
-        store: &B,
-    ) -> Result<OpResult<PublicNode>> {
-        let (directory_path, node_name) = utils::split_last(path_segments)?;
-
-        let mut directory_node_path = match self.get_path_nodes(directory_path, store).await? {
-            PathNodesResult::Complete(node_path) => node_path,
-            _ => bail!(FsError::NotFound),
-        };
-
-        let mut directory = (*directory_node_path.tail).clone();
-
-        // remove the entry from its parent directory
-        let removed_node = match directory.userland.remove(node_name) {
-            Some(link) => link.get_owned_value(store).await?,
-            None => bail!(FsError::NotFound),
-        };
-
-        directory_node_path.tail = Rc::new(directory);
+        store: &mut B,
+    ) -> Result<OpResult<Vec<u8>>> {
+        let OpResult { root_dir, result } = self.read(path_segments, store).await?;
+        let result = content::get_bytes(&result, store).await?;
 
-        Ok(OpResult {
-            root_dir: directory_node_path.reconstruct(),
-            result: removed_node,
-        })
+        Ok(OpResult { root_dir, result })
     }
 
-    /// Moves a file or directory from one path to another.
+    /// Reads up to `len` bytes starting at `offset` from a file written with `write_bytes`.
     ///
-    /// This function requires stating the destination name explicitly.
+    /// Only the content blocks that cover the requested range are fetched from the store.
     ///
     /// # Examples
     ///
     /// ```
     /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
-    /// use libipld::cid::Cid;
     /// use std::rc::Rc;
     /// use chrono::Utc;
     ///
     /// #[async_std::main]
     /// async fn main() {
-    ///     let time = Utc::now();
-    ///     let dir = Rc::new(PublicDirectory::new(time));
-    ///     let store = MemoryBlockStore::default();
+    ///     let mut store = MemoryBlockStore::default();
     ///
     ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
-    ///         .write(
-    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
-    ///             Cid::default(),
-    ///             Utc::now(),
-    ///             &store
-    ///         )
+    ///         .write_bytes(&["hello.txt".into()], b"hello world", Utc::now(), &mut store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir, .. } = root_dir
-    ///         .basic_mv(
-    ///             &["pictures".into(), "cats".into()],
-    ///             &["cats".into()],
-    ///             Utc::now(),
-    ///             &store
-    ///         )
-    ///         .await
-    ///         .unwrap();
-    ///
-    ///     let OpResult { root_dir, result } = root_dir
-    ///         .ls(&[], &store)
+    ///     let OpResult { result, .. } = root_dir
+    ///         .read_range(&["hello.txt".into()], 6, 5, &store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     assert_eq!(result.len(), 2);
+    ///     assert_eq!(result, b"world");
     /// }
     /// ```
-    pub async fn basic_mv<B: BlockStore>(
+    pub async fn read_range<B: BlockStore>(
         self: Rc<Self>,
-        path_segments_from: &[String],
-        path_segments_to: &[String],
-        time: DateTime<Utc>,
+        path_segments: &[String],
+        offset: u64,
+        len: u64,
         store: &B,
-    ) -> Result<OpResult<()>> {
+    ) -> Result<OpResult<Vec<u8>>> {
         let root_dir = Rc::clone(&self);
-        let (directory_path_nodes, filename) = utils::split_last(path_segments_to)?;
-
-        let OpResult {
-            root_dir,
-            result: removed_node,
-        } = root_dir.rm(path_segments_from, store).await?;
-
-        let mut path_nodes = match root_dir.get_path_nodes(directory_path_nodes, store).await? {
-            PathNodesResult::Complete(node_path) => node_path,
-            _ => bail!(FsError::NotFound),
-        };
+        let file = self.get_file(path_segments, store).await?;
+        let result = content::get_range(&file.userland, offset, len, store).await?;
 
-        let mut directory = (*path_nodes.tail).clone();
+        Ok(OpResult { root_dir, result })
+    }
 
-        ensure!(
-            !directory.userland.contains_key(filename),
-            FsError::FileAlreadyExists
-        );
+    /// Gets a stream of the content chunks of a file written with `write_bytes`.
+    ///
+    /// Content blocks are only fetched from the store as the stream is polled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    /// use futures_util::pin_mut;
+    /// use async_std::stream::StreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write_bytes(&["hello.txt".into()], b"hello world", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let path_segments = ["hello.txt".into()];
+    ///     let stream = root_dir.read_stream(&path_segments, &store);
+    ///
+    ///     pin_mut!(stream);
+    ///
+    ///     while let Some(chunk) = stream.next().await {
+    ///         println!("chunk = {:?}", chunk.unwrap());
+    ///     }
+    /// }
+    /// ```
+    pub fn read_stream<'a, B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &'a [String],
+        store: &'a B,
+    ) -> impl Stream<Item = Result<Bytes>> + 'a {
+        try_stream! {
+            let file = self.get_file(path_segments, store).await?;
+            let chunks = content::stream_range(file.userland, 0, u64::MAX, store);
+            pin_mut!(chunks);
 
-        let removed_node = removed_node.update_mtime(time);
+            while let Some(chunk) = chunks.next().await {
+                yield chunk?;
+            }
+        }
+    }
 
-        directory
-            .userland
-            .insert(filename.clone(), PublicLink::new(removed_node));
+    /// Gets the file at the end of the path, following symlinks.
+    async fn get_file<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<Rc<PublicFile>> {
+        utils::split_last(path_segments)?;
+
+        // The file is the first node that isn't a directory, as long as the whole path was used to get to it.
+        let hop_limit = self.symlink_hop_limit;
+        match self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?
+        {
+            (PathNodesResult::NotADirectory(node_path, filename), rest) if rest.is_empty() => {
+                match node_path.tail.lookup_node(&filename, store).await? {
+                    Some(PublicNode::File(file)) => Ok(file),
+                    _ => error(FsError::NotFound),
+                }
+            }
+            (PathNodesResult::Complete(_), _) => error(FsError::NotAFile),
+            _ => error(FsError::NotFound),
+        }
+    }
 
-        path_nodes.tail = Rc::new(directory);
+    /// Creates a new directory at the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, Id, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .mkdir(&["pictures".into(), "cats".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// This method acts like `mkdir -p` in Unix because it creates intermediate directories if they do not exist.
+    pub async fn mkdir<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        let path_nodes = self
+            .get_path_nodes_or_create(path_segments, time, store)
+            .await?;
 
         Ok(OpResult {
-            root_dir: path_nodes.reconstruct(),
+            root_dir: path_nodes
+                .reconstruct_at(time)
+                .complete_usage(store)
+                .await?,
             result: (),
         })
     }
 
-    /// Constructs a tree from directory with `base` as the historical ancestor.
+    /// Returns the name and metadata of the direct children of a directory.
     ///
     /// # Examples
     ///
@@ -819,9 +1160,9 @@ impl PublicDirectory {
     /// async fn main() {
     ///     let time = Utc::now();
     ///     let dir = Rc::new(PublicDirectory::new(time));
-    ///     let mut store = MemoryBlockStore::default();
+    ///     let store = MemoryBlockStore::default();
     ///
-    ///     let OpResult { root_dir: base_root, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
     ///         .write(
     ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
     ///             Cid::default(),
@@ -831,118 +1172,51 @@ impl PublicDirectory {
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir: recent_root, .. } = Rc::clone(&base_root)
-    ///         .write(
-    ///             &["pictures".into(), "cats".into(), "katherine.png".into()],
-    ///             Cid::default(),
-    ///             Utc::now(),
-    ///             &store
-    ///         )
+    ///     let OpResult { root_dir, result } = root_dir
+    ///         .ls(&["pictures".into(), "cats".into()], &store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir: derived_root, .. } = recent_root
-    ///         .base_history_on(base_root, &mut store)
-    ///         .await
-    ///         .unwrap();
+    ///     assert_eq!(result.len(), 1);
+    ///     assert_eq!(result[0].0, "tabby.png");
     /// }
     /// ```
-    pub async fn base_history_on<B: BlockStore>(
+    pub async fn ls<B: BlockStore>(
         self: Rc<Self>,
-        base: Rc<Self>,
-        store: &mut B,
-    ) -> Result<OpResult<()>> {
-        if Rc::ptr_eq(&self, &base) {
-            return Ok(OpResult {
-                root_dir: Rc::clone(&self),
-                result: (),
-            });
-        }
-
-        let mut dir = (*self).clone();
-        dir.previous = Some(base.store(store).await?);
-
-        for (name, entry) in self.userland.iter() {
-            if let Some(base_entry) = base.userland.get(name) {
-                if let Some(new_entry) =
-                    Self::base_history_on_helper(entry, base_entry, store).await?
-                {
-                    dir.userland.insert(name.to_string(), new_entry);
-                }
-            }
-        }
-
-        Ok(OpResult {
-            root_dir: Rc::new(dir),
-            result: (),
-        })
-    }
-
-    /// Constructs a tree from directory with `base` as the historical ancestor.
-    #[async_recursion(?Send)]
-    pub(crate) async fn base_history_on_helper<B: BlockStore>(
-        link: &PublicLink,
-        base_link: &PublicLink,
-        store: &mut B,
-    ) -> Result<Option<PublicLink>> {
-        if link.deep_eq(base_link, store).await? {
-            return Ok(None);
-        }
-
-        let node = link.resolve_value(store).await?;
-        let base_node = base_link.resolve_value(store).await?;
-
-        let (mut dir, dir_rc, base_dir) = match (node, base_node) {
-            (PublicNode::Dir(dir_rc), PublicNode::Dir(base_dir_rc)) => {
-                let mut dir = (**dir_rc).clone();
-                dir.previous = Some(*base_link.resolve_cid(store).await?);
-                (dir, dir_rc, base_dir_rc)
-            }
-            (PublicNode::File(file_rc), PublicNode::File(_)) => {
-                let mut file = (**file_rc).clone();
-                file.previous = Some(*base_link.resolve_cid(store).await?);
-                return Ok(Some(PublicLink::with_file(Rc::new(file))));
-            }
-            _ => {
-                // One is a file and the other is a directory
-                // No need to fix up previous links
-                return Ok(None);
-            }
-        };
-
-        for (name, entry) in dir_rc.userland.iter() {
-            if let Some(base_entry) = base_dir.userland.get(name) {
-                if let Some(new_entry) =
-                    Self::base_history_on_helper(entry, base_entry, store).await?
-                {
-                    dir.userland.insert(name.to_string(), new_entry);
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<OpResult<Vec<(String, Metadata)>>> {
+        let root_dir = Rc::clone(&self);
+        match self.get_path_nodes(path_segments, store).await? {
+            PathNodesResult::Complete(path_nodes) => {
+                let mut result = vec![];
+                for (name, link) in path_nodes.tail.userland.iter() {
+                    let node = link.resolve_value(store).await?;
+                    result.push((name.clone(), node.get_metadata().clone()));
                 }
+                Ok(OpResult { root_dir, result })
             }
+            _ => bail!(FsError::NotFound),
         }
-
-        Ok(Some(PublicLink::with_dir(Rc::new(dir))))
     }
 
-    /// Gets a stream for walking the history of a directory node.
+    /// Removes a file or directory from the directory.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::{rc::Rc, pin::Pin};
-    ///
     /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
     /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
     /// use chrono::Utc;
-    /// use futures_util::pin_mut;
-    /// use async_std::stream::StreamExt;
     ///
     /// #[async_std::main]
     /// async fn main() {
     ///     let time = Utc::now();
     ///     let dir = Rc::new(PublicDirectory::new(time));
-    ///     let mut store = MemoryBlockStore::default();
+    ///     let store = MemoryBlockStore::default();
     ///
-    ///     let OpResult { root_dir: base_root, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
     ///         .write(
     ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
     ///             Cid::default(),
@@ -952,362 +1226,2175 @@ impl PublicDirectory {
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir: recent_root, .. } = Rc::clone(&base_root)
-    ///         .write(
-    ///             &["pictures".into(), "cats".into(), "katherine.png".into()],
-    ///             Cid::default(),
-    ///             Utc::now(),
-    ///             &store
-    ///         )
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .rm(&["pictures".into(), "cats".into()], &store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let OpResult { root_dir: derived_root, .. } = recent_root
-    ///         .base_history_on(base_root, &mut store)
+    ///     let OpResult { root_dir, result } = root_dir
+    ///         .ls(&["pictures".into()], &store)
     ///         .await
     ///         .unwrap();
     ///
-    ///     let history = derived_root.get_history(&store);
-    ///
-    ///     pin_mut!(history);
-    ///
-    ///     while let Some(cid) = history.next().await {
-    ///         println!("previous = {:?}", cid);
-    ///     }
+    ///     assert_eq!(result.len(), 0);
     /// }
     /// ```
-    pub fn get_history<B: BlockStore>(
+    pub async fn rm<B: BlockStore>(
         self: Rc<Self>,
+        path_segments: &[String],
         store: &B,
-    ) -> impl Stream<Item = Result<Cid>> + '_ {
-        let mut working_node = self;
-        try_stream! {
-            while let Some(cid) = working_node.get_previous() {
-                working_node = Rc::new(store.get_deserializable(&cid).await?);
-                yield cid;
-            }
-        }
-    }
-}
-
-impl Id for PublicDirectory {
-    fn get_id(&self) -> String {
-        format!("{:p}", &self.metadata)
+    ) -> Result<OpResult<PublicNode>> {
+        self.rm_helper(path_segments, None, store).await
     }
-}
 
-/// Implements async deserialization for serde serializable types.
-#[async_trait(?Send)]
-impl AsyncSerialize for PublicDirectory {
-    async fn async_serialize<S: Serializer, B: BlockStore + ?Sized>(
-        &self,
-        serializer: S,
-        store: &mut B,
-    ) -> Result<S::Ok, S::Error> {
-        let encoded_userland = {
-            let mut map = BTreeMap::new();
-            for (name, link) in self.userland.iter() {
-                map.insert(
-                    name.clone(),
-                    *link
-                        .resolve_cid(store)
-                        .await
-                        .map_err(|e| SerError::custom(format!("{}", e)))?,
-                );
-            }
-            map
+    /// Removes a file or directory from the directory like [`PublicDirectory::rm`], giving the directories
+    /// along the path `time` as their modified time.
+    ///
+    /// Unlike with [`PublicDirectory::rm`], the resulting root is found by [`PublicDirectory::at_time`] at the
+    /// time of the removal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, PublicNode, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::{Duration, Utc};
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let time = Utc::now();
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+    ///         .mkdir(&["pictures".into(), "cats".into()], time, &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let later = time + Duration::minutes(1);
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .rm_at(&["pictures".into(), "cats".into()], later, &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let root_dir = PublicNode::Dir(root_dir);
+    ///
+    ///     assert_eq!(root_dir.get_metadata().get_modified(), later);
+    /// }
+    /// ```
+    pub async fn rm_at<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<PublicNode>> {
+        self.rm_helper(path_segments, Some(time), store).await
+    }
+
+    /// Removes a file or directory, giving the directories along the path `time` as their modified time if
+    /// there is one.
+    async fn rm_helper<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        time: Option<DateTime<Utc>>,
+        store: &B,
+    ) -> Result<OpResult<PublicNode>> {
+        let (directory_path, node_name) = utils::split_last(path_segments)?;
+
+        let mut directory_node_path = match self.get_path_nodes(directory_path, store).await? {
+            PathNodesResult::Complete(node_path) => node_path,
+            _ => bail!(FsError::NotFound),
         };
 
-        (PublicDirectorySerde {
-            metadata: self.metadata.clone(),
-            userland: encoded_userland,
-            previous: self.previous,
+        let mut directory = (*directory_node_path.tail).clone();
+
+        // remove the entry from its parent directory
+        let removed_node = match directory.replace_entry(node_name, None, store).await? {
+            Some(link) => link.get_owned_value(store).await?,
+            None => bail!(FsError::NotFound),
+        };
+
+        directory_node_path.tail = Rc::new(directory);
+
+        let root_dir = match time {
+            Some(time) => directory_node_path.reconstruct_at(time),
+            None => directory_node_path.reconstruct(),
+        };
+
+        Ok(OpResult {
+            root_dir: root_dir.complete_usage(store).await?,
+            result: removed_node,
         })
-        .serialize(serializer)
     }
-}
-
-impl<'de> Deserialize<'de> for PublicDirectory {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let PublicDirectorySerde {
-            metadata,
-            userland,
-            previous,
-        } = PublicDirectorySerde::deserialize(deserializer)?;
 
-        let decoded_userland = userland
-            .into_iter()
-            .map(|(name, cid)| (name, PublicLink::from_cid(cid)))
-            .collect();
+    /// Moves a file or directory from one path to another.
+    ///
+    /// This function requires stating the destination name explicitly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let time = Utc::now();
+    ///     let dir = Rc::new(PublicDirectory::new(time));
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .basic_mv(
+    ///             &["pictures".into(), "cats".into()],
+    ///             &["cats".into()],
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, result } = root_dir
+    ///         .ls(&[], &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result.len(), 2);
+    /// }
+    /// ```
+    pub async fn basic_mv<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments_from: &[String],
+        path_segments_to: &[String],
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        let OpResult { root_dir, .. } = self
+            .mv(
+                path_segments_from,
+                path_segments_to,
+                MoveMode::NoClobber,
+                time,
+                store,
+            )
+            .await?;
 
-        Ok(Self {
-            metadata,
-            userland: decoded_userland,
-            previous,
+        Ok(OpResult {
+            root_dir,
+            result: (),
         })
     }
-}
 
-//--------------------------------------------------------------------------------------------------
-// Utilities
-//--------------------------------------------------------------------------------------------------
+    /// Moves a file or directory from one path to another, following the given mode when the destination exists.
+    ///
+    /// All checks happen before the tree is changed, so a failed move leaves nothing half done. A directory can't be
+    /// moved into itself or one of its descendants. The moved node keeps its previous revision. The result is the node
+    /// that got replaced when moving with `MoveMode::Overwrite`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{MoveMode, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(&["draft.txt".into()], Cid::default(), Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .write(&["final.txt".into()], Cid::default(), Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, result } = root_dir
+    ///         .mv(
+    ///             &["draft.txt".into()],
+    ///             &["final.txt".into()],
+    ///             MoveMode::Overwrite,
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(result.is_some());
+    /// }
+    /// ```
+    pub async fn mv<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments_from: &[String],
+        path_segments_to: &[String],
+        mode: MoveMode,
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<Option<PublicNode>>> {
+        let (from_path, from_name) = utils::split_last(path_segments_from)?;
+        let (to_path, to_name) = utils::split_last(path_segments_to)?;
 
-mod utils {
-    use anyhow::Result;
+        let from_path_nodes = match Rc::clone(&self).get_path_nodes(from_path, store).await? {
+            PathNodesResult::Complete(node_path) => node_path,
+            _ => bail!(FsError::NotFound),
+        };
 
-    use crate::{error, FsError};
+        let to_path_nodes = match Rc::clone(&self).get_path_nodes(to_path, store).await? {
+            PathNodesResult::Complete(node_path) => node_path,
+            _ => bail!(FsError::NotFound),
+        };
 
-    pub(super) fn split_last(path_segments: &[String]) -> Result<(&[String], &String)> {
-        match path_segments.split_last() {
-            Some((last, rest)) => Ok((rest, last)),
-            None => error(FsError::InvalidPath),
+        let source = match from_path_nodes.tail.lookup_node(from_name, store).await? {
+            Some(node) => node,
+            None => bail!(FsError::NotFound),
+        };
+
+        let destination = to_path_nodes.tail.lookup_node(to_name, store).await?;
+
+        // Compare the paths with symlinks resolved, since that's where the nodes actually are.
+        let from = utils::resolved_path(&from_path_nodes, from_name);
+        let to = utils::resolved_path(&to_path_nodes, to_name);
+
+        if from == to {
+            return Ok(OpResult {
+                root_dir: self,
+                result: None,
+            });
         }
-    }
-}
 
-//--------------------------------------------------------------------------------------------------
-// Tests
-//--------------------------------------------------------------------------------------------------
+        ensure!(
+            !(source.is_dir() && to.starts_with(&from)),
+            FsError::InvalidMoveLocation
+        );
 
-#[cfg(test)]
-mod public_directory_tests {
-    use super::*;
-    use crate::{dagcbor, public::PublicFile, MemoryBlockStore};
-    use chrono::Utc;
+        match (mode, &destination) {
+            (MoveMode::NoClobber, Some(_)) => bail!(FsError::FileAlreadyExists),
+            (MoveMode::Overwrite, Some(destination)) => {
+                utils::ensure_replaceable(&source, destination)?
+            }
+            (MoveMode::Exchange, Some(destination)) => ensure!(
+                !(destination.is_dir() && from.starts_with(&to)),
+                FsError::InvalidMoveLocation
+            ),
+            (MoveMode::Exchange, None) => bail!(FsError::NotFound),
+            _ => (),
+        }
+
+        let replacement = match mode {
+            MoveMode::Exchange => destination.as_ref().map(|node| node.update_mtime(time)),
+            _ => None,
+        };
+
+        let root_dir =
+            utils::set_entry(from_path_nodes, from_name, replacement, Some(time), store).await?;
+
+        // Removing the source can't affect the destination's parent since it isn't inside the source.
+        let to_path_nodes = match root_dir.get_path_nodes(&to[..to.len() - 1], store).await? {
+            PathNodesResult::Complete(node_path) => node_path,
+            _ => bail!(FsError::NotFound),
+        };
+
+        let source = Some(source.update_mtime(time));
+        let root_dir = utils::set_entry(to_path_nodes, to_name, source, Some(time), store).await?;
+
+        let result = match mode {
+            MoveMode::Overwrite => destination,
+            _ => None,
+        };
+
+        Ok(OpResult { root_dir, result })
+    }
+
+    /// Copies a file or directory from one path to another.
+    ///
+    /// The copy reuses the links of the source node, so none of the copied subtree is re-encoded. When `keep_history`
+    /// is false, the copy starts out with no previous revision.
+    ///
+    /// This function requires stating the destination name explicitly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .cp(
+    ///             &["pictures".into(), "cats".into()],
+    ///             &["cats".into()],
+    ///             true,
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .ls(&["cats".into()], &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result.len(), 1);
+    /// }
+    /// ```
+    pub async fn cp<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments_from: &[String],
+        path_segments_to: &[String],
+        keep_history: bool,
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        let (directory_path_from, node_name) = utils::split_last(path_segments_from)?;
+        let (directory_path_to, filename) = utils::split_last(path_segments_to)?;
+
+        let directory_from = match Rc::clone(&self)
+            .get_path_nodes(directory_path_from, store)
+            .await?
+        {
+            PathNodesResult::Complete(node_path) => node_path.tail,
+            _ => bail!(FsError::NotFound),
+        };
+
+        let copied_node = match directory_from.userland.get(node_name) {
+            Some(link) => link.resolve_value(store).await?.update_mtime(time),
+            None => bail!(FsError::NotFound),
+        };
+
+        let copied_node = if keep_history {
+            copied_node
+        } else {
+            copied_node.update_previous(BTreeSet::new())
+        };
+
+        let mut path_nodes = match self.get_path_nodes(directory_path_to, store).await? {
+            PathNodesResult::Complete(node_path) => node_path,
+            _ => bail!(FsError::NotFound),
+        };
+
+        let mut directory = (*path_nodes.tail).clone();
+
+        ensure!(
+            !directory.userland.contains_key(filename),
+            FsError::FileAlreadyExists
+        );
+
+        directory
+            .replace_entry(filename, Some(PublicLink::new(copied_node)), store)
+            .await?;
+
+        path_nodes.tail = Rc::new(directory);
+
+        Ok(OpResult {
+            root_dir: path_nodes
+                .reconstruct_at(time)
+                .complete_usage(store)
+                .await?,
+            result: (),
+        })
+    }
+
+    /// Creates a symlink at the specified path that points to `target`.
+    ///
+    /// Like `write`, this creates intermediate directories if they do not exist. The target doesn't
+    /// have to exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .symlink("/pictures/cats", &["cats".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .read(&["cats".into(), "tabby.png".into()], &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result, Cid::default());
+    /// }
+    /// ```
+    pub async fn symlink<B: BlockStore>(
+        self: Rc<Self>,
+        target: &str,
+        path_segments: &[String],
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        ensure!(!target.is_empty(), FsError::InvalidPath);
+
+        let (directory_path, name) = utils::split_last(path_segments)?;
+
+        let mut path_nodes = self
+            .get_path_nodes_or_create(directory_path, time, store)
+            .await?;
+
+        let mut directory = (*path_nodes.tail).clone();
+
+        ensure!(
+            !directory.userland.contains_key(name),
+            FsError::FileAlreadyExists
+        );
+
+        let symlink = PublicSymlink::new(time, target.to_string());
+        let link = PublicLink::with_symlink(Rc::new(symlink));
+        directory.replace_entry(name, Some(link), store).await?;
+
+        path_nodes.tail = Rc::new(directory);
+
+        Ok(OpResult {
+            root_dir: path_nodes
+                .reconstruct_at(time)
+                .complete_usage(store)
+                .await?,
+            result: (),
+        })
+    }
+
+    /// Gets the target of the symlink at the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .symlink("../videos", &["pictures".into(), "videos".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .readlink(&["pictures".into(), "videos".into()], &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result, "../videos");
+    /// }
+    /// ```
+    pub async fn readlink<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<OpResult<String>> {
+        let root_dir = Rc::clone(&self);
+        let (path, name) = utils::split_last(path_segments)?;
+
+        match self.get_path_nodes(path, store).await? {
+            PathNodesResult::Complete(path_nodes) => {
+                match path_nodes.tail.lookup_node(name, store).await? {
+                    Some(PublicNode::Symlink(symlink)) => Ok(OpResult {
+                        root_dir,
+                        result: symlink.target.clone(),
+                    }),
+                    Some(_) => error(FsError::NotASymlink),
+                    None => error(FsError::NotFound),
+                }
+            }
+            _ => error(FsError::NotFound),
+        }
+    }
+
+    /// Resolves all symlinks in a path and returns the path of the node it leads to.
+    ///
+    /// At most `hop_limit` symlinks are followed. Other operations on paths follow as many as the limit set
+    /// with [`PublicDirectory::with_symlink_hop_limit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore, SYMLINK_HOP_LIMIT};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .mkdir(&["pictures".into(), "cats".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .symlink("pictures/cats", &["cats".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .canonicalize(&["cats".into()], SYMLINK_HOP_LIMIT, &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result, ["pictures", "cats"]);
+    /// }
+    /// ```
+    pub async fn canonicalize<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        hop_limit: usize,
+        store: &B,
+    ) -> Result<OpResult<Vec<String>>> {
+        let root_dir = Rc::clone(&self);
+
+        let (path_nodes, name) = match self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?
+        {
+            (PathNodesResult::Complete(path_nodes), _) => (path_nodes, None),
+            (PathNodesResult::NotADirectory(path_nodes, name), rest) if rest.is_empty() => {
+                (path_nodes, Some(name))
+            }
+            _ => return error(FsError::NotFound),
+        };
+
+        let result = path_nodes
+            .path
+            .into_iter()
+            .map(|(_, segment)| segment)
+            .chain(name)
+            .collect();
+
+        Ok(OpResult { root_dir, result })
+    }
+
+    /// Sets the permissions of the node at the specified path.
+    ///
+    /// Symlinks are followed, so the permissions of the node a symlink points to are changed. An empty path
+    /// changes the permissions of this directory. The modified time of the node is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore, UnixFsMode};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write_bytes(&["run.sh".into()], b"echo hello", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .chmod(&["run.sh".into()], UnixFsMode::new(0o4750).unwrap(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .stat(&["run.sh".into()], &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(result.mode.is_setuid());
+    /// }
+    /// ```
+    pub async fn chmod<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        mode: UnixFsMode,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        self.update_metadata(path_segments, store, |metadata| {
+            metadata.unix_fs.mode = mode;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Sets the creation and modification times of the node at the specified path.
+    ///
+    /// Times that are `None` are left unchanged. Symlinks are followed and an empty path refers to this
+    /// directory. This is meant for tools that copy nodes from elsewhere and need to keep their original times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let created = Utc.timestamp(1_500_000_000, 250_000_000);
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .mkdir(&["pictures".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .set_times(&["pictures".into()], Some(created), Some(created), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .stat(&["pictures".into()], &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result.created, created);
+    ///     assert_eq!(result.modified, created);
+    /// }
+    /// ```
+    pub async fn set_times<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        created: Option<DateTime<Utc>>,
+        modified: Option<DateTime<Utc>>,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        self.update_metadata(path_segments, store, |metadata| {
+            if let Some(created) = created {
+                metadata.set_created(created);
+            }
+
+            if let Some(modified) = modified {
+                metadata.set_modified(modified);
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Gets the metadata of the node at the specified path, following symlinks.
+    pub(crate) async fn resolve_metadata<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<Metadata> {
+        let hop_limit = self.symlink_hop_limit;
+        match self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?
+        {
+            (PathNodesResult::Complete(path_nodes), _) => Ok(path_nodes.tail.metadata.clone()),
+            (PathNodesResult::NotADirectory(path_nodes, name), rest) if rest.is_empty() => {
+                match path_nodes.tail.lookup_node(&name, store).await? {
+                    Some(node) => Ok(node.get_metadata().clone()),
+                    None => error(FsError::NotFound),
+                }
+            }
+            _ => error(FsError::NotFound),
+        }
+    }
+
+    /// Changes the metadata of the node at the specified path, following symlinks.
+    ///
+    /// The tree is left unchanged if `update` fails.
+    pub(crate) async fn update_metadata<B: BlockStore, T>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &B,
+        update: impl FnOnce(&mut Metadata) -> Result<T>,
+    ) -> Result<OpResult<T>> {
+        let hop_limit = self.symlink_hop_limit;
+        let (root_dir, result) = match self
+            .resolve_path_nodes(path_segments, hop_limit, store)
+            .await?
+        {
+            (PathNodesResult::Complete(mut path_nodes), _) => {
+                let mut directory = (*path_nodes.tail).clone();
+                let result = update(&mut directory.metadata)?;
+                path_nodes.tail = Rc::new(directory);
+                (path_nodes.reconstruct(), result)
+            }
+            (PathNodesResult::NotADirectory(path_nodes, name), rest) if rest.is_empty() => {
+                let node = match path_nodes.tail.lookup_node(&name, store).await? {
+                    Some(node) => node,
+                    None => return error(FsError::NotFound),
+                };
+
+                let mut metadata = node.get_metadata().clone();
+                let result = update(&mut metadata)?;
+                let node = node.update_metadata(metadata);
+                let root_dir = utils::set_entry(path_nodes, &name, Some(node), None, store).await?;
+                (root_dir, result)
+            }
+            _ => return error(FsError::NotFound),
+        };
+
+        let root_dir = root_dir.complete_usage(store).await?;
+        Ok(OpResult { root_dir, result })
+    }
+
+    /// Constructs a tree from directory with `base` as the historical ancestor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let time = Utc::now();
+    ///     let dir = Rc::new(PublicDirectory::new(time));
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir: base_root, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: recent_root, .. } = Rc::clone(&base_root)
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "katherine.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: derived_root, .. } = recent_root
+    ///         .base_history_on(base_root, &mut store)
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub async fn base_history_on<B: BlockStore>(
+        self: Rc<Self>,
+        base: Rc<Self>,
+        store: &mut B,
+    ) -> Result<OpResult<()>> {
+        if Rc::ptr_eq(&self, &base) {
+            return Ok(OpResult {
+                root_dir: Rc::clone(&self),
+                result: (),
+            });
+        }
+
+        let mut dir = (*self).clone();
+        dir.previous = BTreeSet::from([base.store(store).await?]);
+
+        for (name, entry) in self.userland.iter() {
+            if let Some(base_entry) = base.userland.get(name) {
+                if let Some(new_entry) =
+                    Self::base_history_on_helper(entry, base_entry, store).await?
+                {
+                    dir.userland.insert(name.to_string(), new_entry);
+                }
+            }
+        }
+
+        Ok(OpResult {
+            root_dir: Rc::new(dir),
+            result: (),
+        })
+    }
+
+    /// Constructs a tree from directory with `base` as the historical ancestor.
+    #[async_recursion(?Send)]
+    pub(crate) async fn base_history_on_helper<B: BlockStore>(
+        link: &PublicLink,
+        base_link: &PublicLink,
+        store: &mut B,
+    ) -> Result<Option<PublicLink>> {
+        if link.deep_eq(base_link, store).await? {
+            return Ok(None);
+        }
+
+        let node = link.resolve_value(store).await?;
+        let base_node = base_link.resolve_value(store).await?;
+
+        let (mut dir, dir_rc, base_dir) = match (node, base_node) {
+            (PublicNode::Dir(dir_rc), PublicNode::Dir(base_dir_rc)) => {
+                let mut dir = (**dir_rc).clone();
+                dir.previous = BTreeSet::from([*base_link.resolve_cid(store).await?]);
+                (dir, dir_rc, base_dir_rc)
+            }
+            (PublicNode::File(file_rc), PublicNode::File(_)) => {
+                let mut file = (**file_rc).clone();
+                file.previous = BTreeSet::from([*base_link.resolve_cid(store).await?]);
+                return Ok(Some(PublicLink::with_file(Rc::new(file))));
+            }
+            (PublicNode::Symlink(symlink_rc), PublicNode::Symlink(_)) => {
+                let mut symlink = (**symlink_rc).clone();
+                symlink.previous = BTreeSet::from([*base_link.resolve_cid(store).await?]);
+                return Ok(Some(PublicLink::with_symlink(Rc::new(symlink))));
+            }
+            _ => {
+                // The nodes are of different kinds
+                // No need to fix up previous links
+                return Ok(None);
+            }
+        };
+
+        for (name, entry) in dir_rc.userland.iter() {
+            if let Some(base_entry) = base_dir.userland.get(name) {
+                if let Some(new_entry) =
+                    Self::base_history_on_helper(entry, base_entry, store).await?
+                {
+                    dir.userland.insert(name.to_string(), new_entry);
+                }
+            }
+        }
+
+        Ok(Some(PublicLink::with_dir(Rc::new(dir))))
+    }
+
+    /// Gets a stream for walking the history of a directory node.
+    ///
+    /// Only the CIDs of the previous revisions of this directory are yielded. Revisions are visited breadth-first
+    /// through all of their `previous` links, and a revision reachable through several merges is yielded once.
+    /// Use [`PublicDirectory::history`] to get the decoded revisions of a path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{rc::Rc, pin::Pin};
+    ///
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use chrono::Utc;
+    /// use futures_util::pin_mut;
+    /// use async_std::stream::StreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let time = Utc::now();
+    ///     let dir = Rc::new(PublicDirectory::new(time));
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir: base_root, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: recent_root, .. } = Rc::clone(&base_root)
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "katherine.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: derived_root, .. } = recent_root
+    ///         .base_history_on(base_root, &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let history = derived_root.get_history(&store);
+    ///
+    ///     pin_mut!(history);
+    ///
+    ///     while let Some(cid) = history.next().await {
+    ///         println!("previous = {:?}", cid);
+    ///     }
+    /// }
+    /// ```
+    pub fn get_history<B: BlockStore>(
+        self: Rc<Self>,
+        store: &B,
+    ) -> impl Stream<Item = Result<Cid>> + '_ {
+        try_stream! {
+            let mut visited = BTreeSet::new();
+            let mut pending = self.previous.iter().copied().collect::<VecDeque<_>>();
+
+            while let Some(cid) = pending.pop_front() {
+                if !visited.insert(cid) {
+                    continue;
+                }
+
+                let dir: PublicDirectory = store.get_deserializable(&cid).await?;
+                pending.extend(dir.previous);
+                yield cid;
+            }
+        }
+    }
+}
+
+impl Id for PublicDirectory {
+    fn get_id(&self) -> String {
+        format!("{:p}", &self.metadata)
+    }
+}
+
+/// Implements async deserialization for serde serializable types.
+#[async_trait(?Send)]
+impl AsyncSerialize for PublicDirectory {
+    async fn async_serialize<S: Serializer, B: BlockStore + ?Sized>(
+        &self,
+        serializer: S,
+        store: &mut B,
+    ) -> Result<S::Ok, S::Error> {
+        let encoded_userland = {
+            let mut map = BTreeMap::new();
+            for (name, link) in self.userland.iter() {
+                map.insert(
+                    name.clone(),
+                    *link
+                        .resolve_cid(store)
+                        .await
+                        .map_err(|e| SerError::custom(format!("{}", e)))?,
+                );
+            }
+            map
+        };
+
+        (PublicDirectorySerde {
+            metadata: self.metadata.clone(),
+            userland: encoded_userland,
+            previous: self.previous.clone(),
+            journal: self.journal,
+        })
+        .serialize(serializer)
+    }
+}
+
+impl PartialEq for PublicDirectory {
+    fn eq(&self, other: &Self) -> bool {
+        // The symlink hop limit isn't part of the tree.
+        self.metadata == other.metadata
+            && self.userland == other.userland
+            && self.previous == other.previous
+            && self.journal == other.journal
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicDirectory {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let PublicDirectorySerde {
+            metadata,
+            userland,
+            previous,
+            journal,
+        } = PublicDirectorySerde::deserialize(deserializer)?;
+
+        let decoded_userland = userland
+            .into_iter()
+            .map(|(name, cid)| (name, PublicLink::from_cid(cid)))
+            .collect();
+
+        Ok(Self {
+            metadata,
+            userland: decoded_userland,
+            previous,
+            journal,
+            symlink_hop_limit: SYMLINK_HOP_LIMIT,
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use std::{rc::Rc, slice};
+
+    use anyhow::Result;
+    use chrono::{DateTime, Utc};
+
+    use crate::{error, validate_segments, BlockStore, FsError};
+
+    use super::{PathNodes, PublicDirectory, PublicLink, PublicNode, Usage};
+
+    /// A segment of a path that is being resolved.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub(super) enum PathSegment {
+        Name(String),
+        Parent,
+    }
+
+    /// Splits a path into the path of a directory and the name of an entry, which has to be valid.
+    pub(super) fn split_last(path_segments: &[String]) -> Result<(&[String], &String)> {
+        match path_segments.split_last() {
+            Some((last, rest)) => {
+                validate_segments(slice::from_ref(last))?;
+                Ok((rest, last))
+            }
+            None => error(FsError::InvalidPath),
+        }
+    }
+
+    /// Gets the path of an entry in the tail of the path nodes.
+    pub(super) fn resolved_path(path_nodes: &PathNodes, name: &str) -> Vec<String> {
+        path_nodes
+            .path
+            .iter()
+            .map(|(_, segment)| segment.clone())
+            .chain([name.to_string()])
+            .collect()
+    }
+
+    /// Sets or removes an entry in the tail of the path nodes and returns the resulting root.
+    ///
+    /// The directories along the path get `time` as their modified time if it is given.
+    pub(super) async fn set_entry<B: BlockStore>(
+        mut path_nodes: PathNodes,
+        name: &str,
+        node: Option<PublicNode>,
+        time: Option<DateTime<Utc>>,
+        store: &B,
+    ) -> Result<Rc<PublicDirectory>> {
+        let mut directory = (*path_nodes.tail).clone();
+        directory
+            .replace_entry(name, node.map(PublicLink::new), store)
+            .await?;
+
+        path_nodes.tail = Rc::new(directory);
+
+        let root_dir = match time {
+            Some(time) => path_nodes.reconstruct_at(time),
+            None => path_nodes.reconstruct(),
+        };
+
+        root_dir.complete_usage(store).await
+    }
+
+    /// Gets what an entry adds to the sizes of a directory without loading it.
+    ///
+    /// A missing entry adds nothing. The usage is `None` if the entry hasn't been loaded.
+    pub(super) fn cached_usage(link: Option<&PublicLink>) -> Option<Usage> {
+        match link {
+            Some(link) => link.get_value().and_then(PublicNode::get_usage),
+            None => Some(Usage::default()),
+        }
+    }
+
+    /// Checks if a node can overwrite another one.
+    pub(super) fn ensure_replaceable(source: &PublicNode, destination: &PublicNode) -> Result<()> {
+        match (source, destination) {
+            (PublicNode::Dir(_), PublicNode::Dir(dir)) if !dir.userland.is_empty() => {
+                error(FsError::DirectoryNotEmpty)
+            }
+            (PublicNode::Dir(_), PublicNode::Dir(_)) => Ok(()),
+            (PublicNode::Dir(_), _) => error(FsError::NotADirectory),
+            (_, PublicNode::Dir(_)) => error(FsError::DirectoryAlreadyExists),
+            _ => Ok(()),
+        }
+    }
+
+    /// Splits a symlink target into segments and tells if it is absolute.
+    pub(super) fn parse_symlink_target(target: &str) -> (bool, Vec<PathSegment>) {
+        let segments = target
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .map(|segment| match segment {
+                ".." => PathSegment::Parent,
+                name => PathSegment::Name(name.to_string()),
+            })
+            .collect();
+
+        (target.starts_with('/'), segments)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod public_directory_tests {
+    use super::*;
+    use crate::{dagcbor, public::PublicFile, MemoryBlockStore};
+    use chrono::{TimeZone, Utc};
+
+    #[async_std::test]
+    async fn look_up_can_fetch_file_added_to_directory() {
+        let root_dir = Rc::new(PublicDirectory::new(Utc::now()));
+        let store = MemoryBlockStore::default();
+        let content_cid = Cid::default();
+        let time = Utc::now();
+
+        let OpResult { root_dir, .. } = root_dir
+            .write(&["text.txt".into()], content_cid, time, &store)
+            .await
+            .unwrap();
+
+        let node = root_dir.lookup_node("text.txt", &store).await.unwrap();
+
+        assert!(node.is_some());
+
+        assert_eq!(
+            node,
+            Some(PublicNode::File(Rc::new(PublicFile::new(
+                time,
+                content_cid
+            ))))
+        );
+    }
+
+    #[async_std::test]
+    async fn look_up_cannot_fetch_file_not_added_to_directory() {
+        let root = PublicDirectory::new(Utc::now());
+        let store = MemoryBlockStore::default();
+
+        let node = root.lookup_node("Unknown", &store).await;
+
+        assert!(node.is_ok());
+
+        assert_eq!(node.unwrap(), None);
+    }
+
+    #[async_std::test]
+    async fn directory_added_to_store_can_be_retrieved() {
+        let root = PublicDirectory::new(Utc::now());
+        let mut store = MemoryBlockStore::default();
+
+        let cid = root.store(&mut store).await.unwrap();
+
+        let encoded_dir = store.get_block(&cid).await.unwrap();
+        let deserialized_dir = dagcbor::decode::<PublicDirectory>(encoded_dir.as_ref()).unwrap();
+
+        assert_eq!(root, deserialized_dir);
+    }
+
+    #[async_std::test]
+    async fn directory_can_encode_decode_as_cbor() {
+        let root = PublicDirectory::new(Utc::now());
+        let store = &mut MemoryBlockStore::default();
+
+        let encoded_dir = dagcbor::async_encode(&root, store).await.unwrap();
+        let decoded_dir = dagcbor::decode::<PublicDirectory>(encoded_dir.as_ref()).unwrap();
+
+        assert_eq!(root, decoded_dir);
+    }
+
+    #[async_std::test]
+    async fn mkdir_can_create_new_directory() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { result, .. } = root_dir
+            .get_node(&["tamedun".into(), "pictures".into()], &store)
+            .await
+            .unwrap();
+
+        assert!(result.is_some());
+    }
+
+    #[async_std::test]
+    async fn ls_can_list_children_under_directory() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .write(
+                &["tamedun".into(), "pictures".into(), "puppy.jpg".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(
+                &["tamedun".into(), "pictures".into(), "cats".into()],
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { result, .. } = root_dir
+            .ls(&["tamedun".into(), "pictures".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+
+        assert_eq!(result[0].0, String::from("cats"));
+
+        assert_eq!(result[1].0, String::from("puppy.jpg"));
+
+        assert_eq!(result[0].1.unix_fs.kind, UnixFsNodeKind::Dir);
+
+        assert_eq!(result[1].1.unix_fs.kind, UnixFsNodeKind::File);
+    }
+
+    #[async_std::test]
+    async fn rm_can_remove_children_from_directory() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .write(
+                &["tamedun".into(), "pictures".into(), "puppy.jpg".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(
+                &["tamedun".into(), "pictures".into(), "cats".into()],
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let result = root_dir
+            .rm(&["tamedun".into(), "pictures".into()], &store)
+            .await;
+
+        assert!(result.is_ok());
+
+        let result = result
+            .unwrap()
+            .root_dir
+            .rm(&["tamedun".into(), "pictures".into()], &store)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[async_std::test]
+    async fn read_can_fetch_userland_of_file_added_to_directory() {
+        let mut store = MemoryBlockStore::default();
+        let content_cid = Cid::default();
+        let time = Utc::now();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write(&["text.txt".into()], content_cid, time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { result, .. } = root_dir
+            .read(&["text.txt".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, content_cid);
+    }
+
+    #[async_std::test]
+    async fn write_bytes_and_read_bytes_round_trip_chunked_content() {
+        let mut store = MemoryBlockStore::default();
+        let time = Utc::now();
+        let content = (0..=255)
+            .cycle()
+            .take(2 * crate::CONTENT_CHUNK_SIZE + 100)
+            .collect::<Vec<u8>>();
+
+        let OpResult {
+            root_dir,
+            result: first_cid,
+        } = Rc::new(PublicDirectory::new(time))
+            .write_bytes(&["first.bin".into()], &content, time, &mut store)
+            .await
+            .unwrap();
+
+        let OpResult {
+            root_dir,
+            result: second_cid,
+        } = root_dir
+            .write_bytes(&["second.bin".into()], &content, time, &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(first_cid, second_cid);
+
+        let OpResult { result, .. } = root_dir
+            .read_bytes(&["first.bin".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, content);
+    }
+
+    #[async_std::test]
+    async fn read_range_and_read_stream_return_requested_content() {
+        let mut store = MemoryBlockStore::default();
+        let time = Utc::now();
+        let content = (0..=255)
+            .cycle()
+            .take(crate::CONTENT_CHUNK_SIZE + 100)
+            .collect::<Vec<u8>>();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write_bytes(&["file.bin".into()], &content, time, &mut store)
+            .await
+            .unwrap();
+
+        let offset = crate::CONTENT_CHUNK_SIZE - 10;
+        let OpResult { root_dir, result } = root_dir
+            .read_range(&["file.bin".into()], offset as u64, 50, &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, &content[offset..offset + 50]);
+
+        let path_segments = ["file.bin".into()];
+        let chunks = root_dir
+            .read_stream(&path_segments, &store)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(chunks.len(), 2);
+
+        let streamed = chunks
+            .into_iter()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .concat();
+
+        assert_eq!(streamed, content);
+    }
+
+    #[async_std::test]
+    async fn write_stream_links_the_same_content_as_write_bytes() {
+        let mut store = MemoryBlockStore::default();
+        let time = Utc::now();
+        let content = (0..=255)
+            .cycle()
+            .take(3 * crate::CONTENT_CHUNK_SIZE)
+            .collect::<Vec<u8>>();
+
+        let OpResult {
+            root_dir,
+            result: streamed_cid,
+        } = Rc::new(PublicDirectory::new(time))
+            .write_stream(&["streamed.bin".into()], &content[..], time, &mut store)
+            .await
+            .unwrap();
+
+        let OpResult {
+            root_dir,
+            result: buffered_cid,
+        } = root_dir
+            .write_bytes(&["buffered.bin".into()], &content, time, &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(streamed_cid, buffered_cid);
+
+        let OpResult { result, .. } = root_dir
+            .read_bytes(&["streamed.bin".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, content);
+    }
+
+    #[async_std::test]
+    async fn path_nodes_can_generates_new_path_nodes() {
+        let store = MemoryBlockStore::default();
+        let now = Utc::now();
+
+        let path_nodes = PathNodes::new(
+            now,
+            &["Documents".into(), "Apps".into()],
+            Rc::new(PublicDirectory::new(now)),
+        );
+
+        let reconstructed = path_nodes.clone().reconstruct();
+
+        let result = reconstructed
+            .get_path_nodes(&["Documents".into(), "Apps".into()], &store)
+            .await
+            .unwrap();
+
+        match result {
+            PathNodesResult::MissingLink(_, segment) => panic!("MissingLink {segment}"),
+            PathNodesResult::NotADirectory(_, segment) => panic!("NotADirectory {segment}"),
+            PathNodesResult::Complete(path_nodes_2) => {
+                assert_eq!(path_nodes.path.len(), path_nodes_2.path.len());
+                assert_eq!(path_nodes.path[0].1, path_nodes_2.path[0].1);
+                assert_eq!(path_nodes.path[1].1, path_nodes_2.path[1].1);
+            }
+        }
+    }
+
+    #[async_std::test]
+    async fn base_history_on_can_create_a_new_derived_tree_pointing_to_base() {
+        let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        let OpResult {
+            root_dir: base_root,
+            ..
+        } = root_dir
+            .write(
+                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult {
+            root_dir: updated_root,
+            ..
+        } = Rc::clone(&base_root)
+            .write(
+                &["pictures".into(), "cats".into(), "luna.png".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult {
+            root_dir: derived_root,
+            ..
+        } = updated_root
+            .base_history_on(Rc::clone(&base_root), &mut store)
+            .await
+            .unwrap();
+
+        // Assert that the root node points to its old version.
+        let derived_previous_cid = derived_root.get_previous();
+        let base_cid = base_root.store(&mut store).await.unwrap();
+
+        assert_eq!(derived_previous_cid, &BTreeSet::from([base_cid]));
+
+        // Assert that some node that exists between versions points to its old version.
+        let OpResult {
+            result: derived_node,
+            ..
+        } = Rc::clone(&derived_root)
+            .get_node(&["pictures".into(), "cats".into()], &store)
+            .await
+            .unwrap();
+
+        let OpResult {
+            result: base_node, ..
+        } = base_root
+            .get_node(&["pictures".into(), "cats".into()], &store)
+            .await
+            .unwrap();
+
+        assert!(derived_node.is_some());
+        assert!(base_node.is_some());
+
+        let derived_node = derived_node.unwrap();
+        let derived_previous_cid = derived_node.get_previous();
+        let base_cid = base_node.unwrap().store(&mut store).await.unwrap();
+
+        assert_eq!(derived_previous_cid, &BTreeSet::from([base_cid]));
+
+        // Assert that some node that doesn't exists between versions does not point to anything.
+        let OpResult {
+            result: derived_node,
+            ..
+        } = Rc::clone(&derived_root)
+            .get_node(
+                &["pictures".into(), "cats".into(), "luna.png".into()],
+                &store,
+            )
+            .await
+            .unwrap();
+
+        assert!(derived_node.is_some());
+        assert!(derived_node.unwrap().get_previous().is_empty());
+    }
+
+    #[async_std::test]
+    async fn mv_can_move_sub_directory_to_another_valid_location() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = root_dir
+            .write(
+                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .write(
+                &["pictures".into(), "cats".into(), "luna.png".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(&["images".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .basic_mv(
+                &["pictures".into(), "cats".into()],
+                &["images".into(), "cats".into()],
+                Utc::now(),
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, result } = root_dir.ls(&["images".into()], &store).await.unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].0, String::from("cats"));
+
+        let OpResult { result, .. } = root_dir.ls(&["pictures".into()], &store).await.unwrap();
+
+        assert_eq!(result.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn mv_cannot_move_sub_directory_to_invalid_location() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(
+                &[
+                    "videos".into(),
+                    "movies".into(),
+                    "anime".into(),
+                    "ghibli".into(),
+                ],
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let result = root_dir
+            .basic_mv(
+                &["videos".into(), "movies".into()],
+                &["videos".into(), "movies".into(), "anime".into()],
+                Utc::now(),
+                &store,
+            )
+            .await;
+
+        assert!(result.is_err());
+    }
 
     #[async_std::test]
-    async fn look_up_can_fetch_file_added_to_directory() {
-        let root_dir = Rc::new(PublicDirectory::new(Utc::now()));
-        let store = MemoryBlockStore::default();
-        let content_cid = Cid::default();
+    async fn mv_can_rename_directories() {
         let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
 
         let OpResult { root_dir, .. } = root_dir
-            .write(&["text.txt".into()], content_cid, time, &store)
+            .write(&["file.txt".into()], Cid::default(), time, &store)
             .await
             .unwrap();
 
-        let node = root_dir.lookup_node("text.txt", &store).await.unwrap();
+        let OpResult { root_dir, .. } = root_dir
+            .basic_mv(
+                &["file.txt".into()],
+                &["renamed.txt".into()],
+                Utc::now(),
+                &store,
+            )
+            .await
+            .unwrap();
 
-        assert!(node.is_some());
+        let OpResult { result, .. } = root_dir
+            .read(&["renamed.txt".into()], &mut store)
+            .await
+            .unwrap();
 
-        assert_eq!(
-            node,
-            Some(PublicNode::File(Rc::new(PublicFile::new(
-                time,
-                content_cid
-            ))))
-        );
+        assert!(result == Cid::default());
     }
 
     #[async_std::test]
-    async fn look_up_cannot_fetch_file_not_added_to_directory() {
-        let root = PublicDirectory::new(Utc::now());
+    async fn cp_shares_the_copied_subtree_with_the_source() {
+        let time = Utc::now();
         let store = MemoryBlockStore::default();
 
-        let node = root.lookup_node("Unknown", &store).await;
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write(
+                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
 
-        assert!(node.is_ok());
+        let OpResult { root_dir, .. } = root_dir
+            .cp(
+                &["pictures".into(), "cats".into()],
+                &["cats".into()],
+                true,
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
 
-        assert_eq!(node.unwrap(), None);
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .get_node(&["cats".into(), "tabby.jpg".into()], &store)
+            .await
+            .unwrap();
+
+        let copied_file = result.unwrap().as_file().unwrap();
+
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .get_node(
+                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let original_file = result.unwrap().as_file().unwrap();
+
+        assert!(Rc::ptr_eq(&copied_file, &original_file));
+
+        let result = root_dir
+            .cp(
+                &["pictures".into(), "cats".into()],
+                &["cats".into()],
+                true,
+                time,
+                &store,
+            )
+            .await;
+
+        assert!(result.is_err());
     }
 
     #[async_std::test]
-    async fn directory_added_to_store_can_be_retrieved() {
-        let root = PublicDirectory::new(Utc::now());
+    async fn cp_can_keep_or_clear_previous_of_the_copy() {
+        let time = Utc::now();
         let mut store = MemoryBlockStore::default();
 
-        let cid = root.store(&mut store).await.unwrap();
+        let OpResult {
+            root_dir: base_root,
+            ..
+        } = Rc::new(PublicDirectory::new(time))
+            .write(&["file.txt".into()], Cid::default(), time, &store)
+            .await
+            .unwrap();
 
-        let encoded_dir = store.get_block(&cid).await.unwrap();
-        let deserialized_dir = dagcbor::decode::<PublicDirectory>(encoded_dir.as_ref()).unwrap();
+        let OpResult { root_dir, .. } = Rc::clone(&base_root)
+            .write(
+                &["file.txt".into()],
+                Cid::default(),
+                time + chrono::Duration::seconds(1),
+                &store,
+            )
+            .await
+            .unwrap();
 
-        assert_eq!(root, deserialized_dir);
+        let OpResult { root_dir, .. } = root_dir
+            .base_history_on(base_root, &mut store)
+            .await
+            .unwrap();
+
+        for (keep_history, name) in [(true, "kept.txt"), (false, "cleared.txt")] {
+            let OpResult { root_dir, .. } = Rc::clone(&root_dir)
+                .cp(
+                    &["file.txt".into()],
+                    &[name.into()],
+                    keep_history,
+                    time,
+                    &store,
+                )
+                .await
+                .unwrap();
+
+            let OpResult { result, .. } = root_dir.get_node(&[name.into()], &store).await.unwrap();
+
+            assert_eq!(!result.unwrap().get_previous().is_empty(), keep_history);
+        }
     }
 
     #[async_std::test]
-    async fn directory_can_encode_decode_as_cbor() {
-        let root = PublicDirectory::new(Utc::now());
-        let store = &mut MemoryBlockStore::default();
+    async fn mv_fails_moving_directories_to_files() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
 
-        let encoded_dir = dagcbor::async_encode(&root, store).await.unwrap();
-        let decoded_dir = dagcbor::decode::<PublicDirectory>(encoded_dir.as_ref()).unwrap();
+        let OpResult { root_dir, .. } = root_dir
+            .mkdir(&["movies".into(), "ghibli".into()], time, &store)
+            .await
+            .unwrap();
 
-        assert_eq!(root, decoded_dir);
+        let OpResult { root_dir, .. } = root_dir
+            .write(&["file.txt".into()], Cid::default(), time, &store)
+            .await
+            .unwrap();
+
+        let result = root_dir
+            .basic_mv(
+                &["movies".into(), "ghibli".into()],
+                &["file.txt".into()],
+                Utc::now(),
+                &store,
+            )
+            .await;
+
+        assert!(result.is_err());
     }
 
     #[async_std::test]
-    async fn mkdir_can_create_new_directory() {
+    async fn mv_checks_everything_before_changing_the_tree() {
         let time = Utc::now();
         let store = MemoryBlockStore::default();
 
         let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
-            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .write(
+                &["movies".into(), "ghibli".into(), "totoro.mp4".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
             .await
             .unwrap();
 
-        let OpResult { result, .. } = root_dir
-            .get_node(&["tamedun".into(), "pictures".into()], &store)
+        let OpResult { root_dir, .. } = root_dir
+            .write(&["file.txt".into()], Cid::default(), time, &store)
             .await
             .unwrap();
 
-        assert!(result.is_some());
+        let failures = [
+            (
+                vec!["movies"],
+                vec!["movies", "ghibli", "movies"],
+                MoveMode::Overwrite,
+            ),
+            (
+                vec!["file.txt"],
+                vec!["missing", "file.txt"],
+                MoveMode::Overwrite,
+            ),
+            (vec!["file.txt"], vec!["movies"], MoveMode::Overwrite),
+            (
+                vec!["movies", "ghibli"],
+                vec!["file.txt"],
+                MoveMode::Overwrite,
+            ),
+            (vec!["file.txt"], vec!["moved.txt"], MoveMode::Exchange),
+            (
+                vec!["movies", "ghibli", "totoro.mp4"],
+                vec!["movies"],
+                MoveMode::Exchange,
+            ),
+        ];
+
+        for (from, to, mode) in failures {
+            let from = from.into_iter().map(String::from).collect::<Vec<_>>();
+            let to = to.into_iter().map(String::from).collect::<Vec<_>>();
+
+            let result = Rc::clone(&root_dir)
+                .mv(&from, &to, mode, time, &store)
+                .await;
+
+            assert!(result.is_err(), "{from:?} -> {to:?}");
+        }
+
+        let OpResult { result, .. } = Rc::clone(&root_dir).ls(&[], &store).await.unwrap();
+
+        assert_eq!(result.len(), 2);
+
+        // Moving a node onto itself is a no-op.
+        let OpResult {
+            root_dir: same_root,
+            ..
+        } = Rc::clone(&root_dir)
+            .mv(
+                &["file.txt".into()],
+                &["file.txt".into()],
+                MoveMode::NoClobber,
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        assert!(Rc::ptr_eq(&same_root, &root_dir));
     }
 
     #[async_std::test]
-    async fn ls_can_list_children_under_directory() {
+    async fn mv_can_overwrite_or_exchange_destinations() {
         let time = Utc::now();
-        let store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        let mut store = MemoryBlockStore::default();
+
+        let first_cid = content::put_bytes(b"first", &mut store).await.unwrap();
+        let second_cid = content::put_bytes(b"second", &mut store).await.unwrap();
+
+        let OpResult {
+            root_dir: base_root,
+            ..
+        } = Rc::new(PublicDirectory::new(time))
+            .write(&["first.txt".into()], first_cid, time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = Rc::clone(&base_root)
+            .write(
+                &["first.txt".into()],
+                first_cid,
+                time + chrono::Duration::seconds(1),
+                &store,
+            )
+            .await
+            .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .base_history_on(base_root, &mut store)
             .await
             .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
             .write(
-                &["tamedun".into(), "pictures".into(), "puppy.jpg".into()],
-                Cid::default(),
+                &["docs".into(), "second.txt".into()],
+                second_cid,
                 time,
                 &store,
             )
             .await
             .unwrap();
 
-        let OpResult { root_dir, .. } = root_dir
-            .mkdir(
-                &["tamedun".into(), "pictures".into(), "cats".into()],
+        let OpResult {
+            root_dir: exchanged_root,
+            result,
+        } = Rc::clone(&root_dir)
+            .mv(
+                &["first.txt".into()],
+                &["docs".into(), "second.txt".into()],
+                MoveMode::Exchange,
                 time,
                 &store,
             )
             .await
             .unwrap();
 
-        let OpResult { result, .. } = root_dir
-            .ls(&["tamedun".into(), "pictures".into()], &store)
+        assert!(result.is_none());
+
+        let OpResult { result, .. } = Rc::clone(&exchanged_root)
+            .read(&["first.txt".into()], &mut store)
             .await
             .unwrap();
 
-        assert_eq!(result.len(), 2);
+        assert_eq!(result, second_cid);
 
-        assert_eq!(result[0].0, String::from("cats"));
+        let OpResult { result, .. } = exchanged_root
+            .read(&["docs".into(), "second.txt".into()], &mut store)
+            .await
+            .unwrap();
 
-        assert_eq!(result[1].0, String::from("puppy.jpg"));
+        assert_eq!(result, first_cid);
 
-        assert_eq!(result[0].1.unix_fs.kind, UnixFsNodeKind::Dir);
+        let OpResult { root_dir, result } = root_dir
+            .mv(
+                &["first.txt".into()],
+                &["docs".into(), "second.txt".into()],
+                MoveMode::Overwrite,
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
 
-        assert_eq!(result[1].1.unix_fs.kind, UnixFsNodeKind::File);
+        assert_eq!(result.unwrap().as_file().unwrap().userland, second_cid);
+
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .get_node(&["docs".into(), "second.txt".into()], &store)
+            .await
+            .unwrap();
+
+        let moved_file = result.unwrap().as_file().unwrap();
+
+        assert_eq!(moved_file.userland, first_cid);
+        assert!(!moved_file.previous.is_empty());
+        assert!(root_dir
+            .lookup_node("first.txt", &store)
+            .await
+            .unwrap()
+            .is_none());
     }
 
     #[async_std::test]
-    async fn rm_can_remove_children_from_directory() {
+    async fn symlinks_are_followed_along_paths() {
         let time = Utc::now();
-        let store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        let mut store = MemoryBlockStore::default();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write(
+                &["pictures".into(), "cats".into(), "tabby.png".into()],
+                Cid::default(),
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(&["tamedun".into(), "pictures".into()], time, &store)
+            .symlink("/pictures/cats", &["cats".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .symlink(
+                "../cats/./tabby.png",
+                &["pictures".into(), "favourite.png".into()],
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .read(&["pictures".into(), "favourite.png".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, Cid::default());
+
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .canonicalize(
+                &["pictures".into(), "favourite.png".into()],
+                SYMLINK_HOP_LIMIT,
+                &store,
+            )
             .await
             .unwrap();
 
+        assert_eq!(result, ["pictures", "cats", "tabby.png"]);
+
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .readlink(&["pictures".into(), "favourite.png".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, "../cats/./tabby.png");
+
+        // Writing to a symlink writes to the file it points to.
+        let content_cid = content::put_bytes(b"tabby", &mut store).await.unwrap();
         let OpResult { root_dir, .. } = root_dir
             .write(
-                &["tamedun".into(), "pictures".into(), "puppy.jpg".into()],
-                Cid::default(),
+                &["pictures".into(), "favourite.png".into()],
+                content_cid,
                 time,
                 &store,
             )
             .await
             .unwrap();
 
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .read(
+                &["pictures".into(), "cats".into(), "tabby.png".into()],
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result, content_cid);
+
+        // Writing through a symlinked directory changes the directory it points to.
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(
-                &["tamedun".into(), "pictures".into(), "cats".into()],
+            .write(
+                &["cats".into(), "luna.png".into()],
+                Cid::default(),
                 time,
                 &store,
             )
             .await
             .unwrap();
 
-        let result = root_dir
-            .rm(&["tamedun".into(), "pictures".into()], &store)
-            .await;
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .ls(&["pictures".into(), "cats".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert!(matches!(
+            root_dir.lookup_node("cats", &store).await.unwrap(),
+            Some(PublicNode::Symlink(_))
+        ));
+    }
+
+    #[async_std::test]
+    async fn operations_reject_invalid_path_segments() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(time));
+
+        for segment in ["", ".", "..", "a/b", "a\0b"] {
+            let expected = FsError::InvalidPathSegment(segment.into());
 
-        assert!(result.is_ok());
+            let error = Rc::clone(&root_dir)
+                .write(&[segment.into()], Cid::default(), time, &store)
+                .await
+                .unwrap_err();
 
-        let result = result
-            .unwrap()
-            .root_dir
-            .rm(&["tamedun".into(), "pictures".into()], &store)
-            .await;
+            assert_eq!(error.downcast_ref::<FsError>(), Some(&expected));
 
-        assert!(result.is_err());
+            let error = Rc::clone(&root_dir)
+                .mkdir(&["docs".into(), segment.into()], time, &store)
+                .await
+                .unwrap_err();
+
+            assert_eq!(error.downcast_ref::<FsError>(), Some(&expected));
+
+            let error = Rc::clone(&root_dir)
+                .ls(&[segment.into(), "docs".into()], &store)
+                .await
+                .unwrap_err();
+
+            assert_eq!(error.downcast_ref::<FsError>(), Some(&expected));
+        }
     }
 
     #[async_std::test]
-    async fn read_can_fetch_userland_of_file_added_to_directory() {
-        let mut store = MemoryBlockStore::default();
-        let content_cid = Cid::default();
+    async fn symlink_loops_are_detected() {
         let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
 
         let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
-            .write(&["text.txt".into()], content_cid, time, &store)
+            .symlink("b", &["a".into()], time, &store)
             .await
             .unwrap();
 
-        let OpResult { result, .. } = root_dir
-            .read(&["text.txt".into()], &mut store)
+        let OpResult { root_dir, .. } = root_dir
+            .symlink("a", &["b".into()], time, &store)
             .await
             .unwrap();
 
-        assert_eq!(result, content_cid);
-    }
+        let error = Rc::clone(&root_dir)
+            .read(&["a".into()], &mut store)
+            .await
+            .unwrap_err();
 
-    #[async_std::test]
-    async fn path_nodes_can_generates_new_path_nodes() {
-        let store = MemoryBlockStore::default();
-        let now = Utc::now();
+        assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::SymlinkLoop));
 
-        let path_nodes = PathNodes::new(
-            now,
-            &["Documents".into(), "Apps".into()],
-            Rc::new(PublicDirectory::new(now)),
-        );
+        let error = Rc::clone(&root_dir)
+            .canonicalize(&["a".into(), "file.txt".into()], 1, &store)
+            .await
+            .unwrap_err();
 
-        let reconstructed = path_nodes.clone().reconstruct();
+        assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::SymlinkLoop));
 
-        let result = reconstructed
-            .get_path_nodes(&["Documents".into(), "Apps".into()], &store)
+        let OpResult { root_dir, .. } = Rc::new((*root_dir).clone().with_symlink_hop_limit(0))
+            .symlink("docs", &["c".into()], time, &store)
             .await
             .unwrap();
 
-        match result {
-            PathNodesResult::MissingLink(_, segment) => panic!("MissingLink {segment}"),
-            PathNodesResult::NotADirectory(_, segment) => panic!("NotADirectory {segment}"),
-            PathNodesResult::Complete(path_nodes_2) => {
-                assert_eq!(path_nodes.path.len(), path_nodes_2.path.len());
-                assert_eq!(path_nodes.path[0].1, path_nodes_2.path[0].1);
-                assert_eq!(path_nodes.path[1].1, path_nodes_2.path[1].1);
-            }
-        }
+        assert_eq!(root_dir.get_symlink_hop_limit(), 0);
+        assert_eq!(
+            *root_dir,
+            (*root_dir)
+                .clone()
+                .with_symlink_hop_limit(SYMLINK_HOP_LIMIT)
+        );
+
+        let error = root_dir
+            .mkdir(&["c".into(), "drafts".into()], time, &store)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::SymlinkLoop));
     }
 
     #[async_std::test]
-    async fn base_history_on_can_create_a_new_derived_tree_pointing_to_base() {
+    async fn chmod_sets_modes_through_symlinks() {
         let time = Utc::now();
-        let mut store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        let store = MemoryBlockStore::default();
 
-        let OpResult {
-            root_dir: base_root,
-            ..
-        } = root_dir
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
             .write(
-                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
+                &["bin".into(), "run.sh".into()],
                 Cid::default(),
                 time,
                 &store,
@@ -1315,212 +3402,282 @@ mod public_directory_tests {
             .await
             .unwrap();
 
-        let OpResult {
-            root_dir: updated_root,
-            ..
-        } = Rc::clone(&base_root)
-            .write(
-                &["pictures".into(), "cats".into(), "luna.png".into()],
-                Cid::default(),
-                time,
-                &store,
-            )
+        let OpResult { root_dir, .. } = root_dir
+            .symlink("bin/run.sh", &["run".into()], time, &store)
             .await
             .unwrap();
 
-        let OpResult {
-            root_dir: derived_root,
-            ..
-        } = updated_root
-            .base_history_on(Rc::clone(&base_root), &mut store)
+        let OpResult { root_dir, .. } = root_dir
+            .chmod(&["run".into()], UnixFsMode::new(0o4750).unwrap(), &store)
             .await
             .unwrap();
 
-        // Assert that the root node points to its old version.
-        let derived_previous_cid = derived_root.get_previous();
-        let base_cid = base_root.store(&mut store).await.unwrap();
-
-        assert!(derived_previous_cid.is_some());
-        assert_eq!(derived_previous_cid.unwrap(), base_cid);
-
-        // Assert that some node that exists between versions points to its old version.
-        let OpResult {
-            result: derived_node,
-            ..
-        } = Rc::clone(&derived_root)
-            .get_node(&["pictures".into(), "cats".into()], &store)
+        let OpResult { root_dir, .. } = root_dir
+            .chmod(&["bin".into()], UnixFsMode::new(0o1777).unwrap(), &store)
             .await
             .unwrap();
 
-        let OpResult {
-            result: base_node, ..
-        } = base_root
-            .get_node(&["pictures".into(), "cats".into()], &store)
+        let file = Rc::clone(&root_dir)
+            .get_node(&["bin".into(), "run.sh".into()], &store)
             .await
+            .unwrap()
+            .result
             .unwrap();
 
-        assert!(derived_node.is_some());
-        assert!(base_node.is_some());
+        assert_eq!(file.get_metadata().get_mode().bits(), 0o4750);
+        assert_eq!(file.get_metadata().unix_fs.modified, time.timestamp());
 
-        let derived_previous_cid = derived_node.unwrap().get_previous();
-        let base_cid = base_node.unwrap().store(&mut store).await.unwrap();
+        let bin = Rc::clone(&root_dir)
+            .get_node(&["bin".into()], &store)
+            .await
+            .unwrap()
+            .result
+            .unwrap();
 
-        assert!(derived_previous_cid.is_some());
-        assert_eq!(derived_previous_cid.unwrap(), base_cid);
+        assert!(bin.get_metadata().get_mode().is_sticky());
 
-        // Assert that some node that doesn't exists between versions does not point to anything.
-        let OpResult {
-            result: derived_node,
-            ..
-        } = Rc::clone(&derived_root)
-            .get_node(
-                &["pictures".into(), "cats".into(), "luna.png".into()],
-                &store,
-            )
+        let error = root_dir
+            .chmod(&["missing".into()], UnixFsMode::new(0o644).unwrap(), &store)
             .await
-            .unwrap();
+            .unwrap_err();
 
-        assert!(derived_node.is_some());
-        assert!(matches!(derived_node.unwrap().get_previous(), None));
+        assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::NotFound));
     }
 
     #[async_std::test]
-    async fn mv_can_move_sub_directory_to_another_valid_location() {
-        let time = Utc::now();
+    async fn overwrites_keep_created_and_set_times_changes_times() {
+        let created = Utc.timestamp(1_600_000_000, 500);
+        let modified = Utc.timestamp(1_650_000_000, 750_000_000);
         let store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
 
-        let OpResult { root_dir, .. } = root_dir
-            .write(
-                &["pictures".into(), "cats".into(), "tabby.jpg".into()],
-                Cid::default(),
-                time,
-                &store,
-            )
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(created))
+            .write(&["text.txt".into()], Cid::default(), created, &store)
             .await
             .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .write(
-                &["pictures".into(), "cats".into(), "luna.png".into()],
-                Cid::default(),
-                time,
+            .chmod(
+                &["text.txt".into()],
+                UnixFsMode::new(0o600).unwrap(),
                 &store,
             )
             .await
             .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(&["images".into()], time, &store)
+            .write(&["text.txt".into()], Cid::default(), modified, &store)
             .await
             .unwrap();
 
-        let OpResult { root_dir, .. } = root_dir
-            .basic_mv(
-                &["pictures".into(), "cats".into()],
-                &["images".into(), "cats".into()],
-                Utc::now(),
-                &store,
-            )
+        let metadata = Rc::clone(&root_dir)
+            .resolve_metadata(&["text.txt".into()], &store)
             .await
             .unwrap();
 
-        let OpResult { root_dir, result } = root_dir.ls(&["images".into()], &store).await.unwrap();
+        assert_eq!(metadata.get_created(), created);
+        assert_eq!(metadata.get_modified(), modified);
+        assert_eq!(metadata.get_mode().bits(), 0o600);
 
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].0, String::from("cats"));
+        let OpResult { root_dir, .. } = root_dir
+            .set_times(&["text.txt".into()], None, Some(created), &store)
+            .await
+            .unwrap();
 
-        let OpResult { result, .. } = root_dir.ls(&["pictures".into()], &store).await.unwrap();
+        let metadata = root_dir
+            .resolve_metadata(&["text.txt".into()], &store)
+            .await
+            .unwrap();
 
-        assert_eq!(result.len(), 0);
+        assert_eq!(metadata.get_created(), created);
+        assert_eq!(metadata.get_modified(), created);
     }
 
     #[async_std::test]
-    async fn mv_cannot_move_sub_directory_to_invalid_location() {
+    async fn directories_keep_aggregated_sizes_up_to_date() {
         let time = Utc::now();
-        let store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        let mut store = MemoryBlockStore::default();
+
+        async fn usage(
+            root_dir: &Rc<PublicDirectory>,
+            path: &[String],
+            store: &MemoryBlockStore,
+        ) -> (Option<u64>, Option<u64>) {
+            let metadata = Rc::clone(root_dir)
+                .resolve_metadata(path, store)
+                .await
+                .unwrap();
+
+            (metadata.get_size(), metadata.get_descendants())
+        }
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write_bytes(
+                &["a".into(), "b".into(), "x.txt".into()],
+                b"hello",
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(
-                &[
-                    "videos".into(),
-                    "movies".into(),
-                    "anime".into(),
-                    "ghibli".into(),
-                ],
+            .write_bytes(&["a".into(), "y.txt".into()], b"hey", time, &mut store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .symlink("y.txt", &["a".into(), "link".into()], time, &store)
+            .await
+            .unwrap();
+
+        assert_eq!(usage(&root_dir, &[], &store).await, (Some(8), Some(5)));
+
+        // Sizes stored with the tree are kept up to date when the tree is loaded again.
+        let cid = root_dir.store(&mut store).await.unwrap();
+        let root_dir = Rc::new(
+            store
+                .get_deserializable::<PublicDirectory>(&cid)
+                .await
+                .unwrap(),
+        );
+
+        let OpResult { root_dir, .. } = root_dir
+            .write_bytes(
+                &["a".into(), "b".into(), "x.txt".into()],
+                b"hello world",
                 time,
-                &store,
+                &mut store,
             )
             .await
             .unwrap();
 
-        let result = root_dir
-            .basic_mv(
-                &["videos".into(), "movies".into()],
-                &["videos".into(), "movies".into(), "anime".into()],
-                Utc::now(),
+        assert_eq!(usage(&root_dir, &[], &store).await, (Some(14), Some(5)));
+
+        let OpResult { root_dir, .. } = root_dir
+            .mv(
+                &["a".into(), "b".into()],
+                &["c".into()],
+                MoveMode::NoClobber,
+                time,
                 &store,
             )
-            .await;
-
-        assert!(result.is_err());
-    }
+            .await
+            .unwrap();
 
-    #[async_std::test]
-    async fn mv_can_rename_directories() {
-        let time = Utc::now();
-        let mut store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        assert_eq!(usage(&root_dir, &[], &store).await, (Some(14), Some(5)));
+        assert_eq!(
+            usage(&root_dir, &["a".into()], &store).await,
+            (Some(3), Some(2))
+        );
 
         let OpResult { root_dir, .. } = root_dir
-            .write(&["file.txt".into()], Cid::default(), time, &store)
+            .cp(&["c".into()], &["d".into()], true, time, &store)
             .await
             .unwrap();
 
+        let OpResult { root_dir, .. } = root_dir.rm(&["c".into()], &store).await.unwrap();
+
+        assert_eq!(usage(&root_dir, &[], &store).await, (Some(14), Some(5)));
+
+        // A file whose content isn't in the store has an unknown size, and so do the totals above it.
         let OpResult { root_dir, .. } = root_dir
-            .basic_mv(
-                &["file.txt".into()],
-                &["renamed.txt".into()],
-                Utc::now(),
+            .write(
+                &["d".into(), "unknown".into()],
+                Cid::default(),
+                time,
                 &store,
             )
             .await
             .unwrap();
 
-        let OpResult { result, .. } = root_dir
-            .read(&["renamed.txt".into()], &mut store)
+        assert_eq!(usage(&root_dir, &[], &store).await, (None, Some(6)));
+
+        // Directories count the files of unknown size below them, so the counts survive encoding and later
+        // operations don't have to add up the sizes again.
+        let cid = root_dir.store(&mut store).await.unwrap();
+        let decoded = store
+            .get_deserializable::<PublicDirectory>(&cid)
             .await
             .unwrap();
 
-        assert!(result == Cid::default());
+        assert_eq!(
+            decoded.get_totals(),
+            Some(Usage {
+                size: 14,
+                nodes: 6,
+                unknown_sizes: 1
+            })
+        );
+
+        // The totals are known again once the file is gone.
+        let OpResult { root_dir, .. } = root_dir
+            .rm(&["d".into(), "unknown".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(usage(&root_dir, &[], &store).await, (Some(14), Some(5)));
+        assert_eq!(
+            usage(&root_dir, &["d".into()], &store).await,
+            (Some(11), Some(1))
+        );
     }
 
     #[async_std::test]
-    async fn mv_fails_moving_directories_to_files() {
+    async fn sizes_of_directories_encoded_without_them_are_recomputed() {
         let time = Utc::now();
-        let store = MemoryBlockStore::default();
-        let root_dir = Rc::new(PublicDirectory::new(time));
+        let mut store = MemoryBlockStore::default();
+
+        fn without_sizes(dir: &PublicDirectory) -> Rc<PublicDirectory> {
+            let mut dir = dir.clone();
+            dir.metadata.size = None;
+            dir.metadata.descendants = None;
+
+            for link in dir.userland.values_mut() {
+                if let Some(PublicNode::Dir(child)) = link.get_value() {
+                    *link = PublicLink::with_dir(without_sizes(child));
+                }
+            }
+
+            Rc::new(dir)
+        }
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write_bytes(
+                &["a".into(), "b".into(), "x.txt".into()],
+                b"hello",
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap();
 
         let OpResult { root_dir, .. } = root_dir
-            .mkdir(&["movies".into(), "ghibli".into()], time, &store)
+            .write_bytes(&["a".into(), "y.txt".into()], b"hey", time, &mut store)
             .await
             .unwrap();
 
+        let cid = without_sizes(&root_dir).store(&mut store).await.unwrap();
+        let root_dir = Rc::new(
+            store
+                .get_deserializable::<PublicDirectory>(&cid)
+                .await
+                .unwrap(),
+        );
+
+        assert_eq!(root_dir.metadata.get_size(), None);
+
         let OpResult { root_dir, .. } = root_dir
-            .write(&["file.txt".into()], Cid::default(), time, &store)
+            .write_bytes(&["z.txt".into()], b"zz", time, &mut store)
             .await
             .unwrap();
 
-        let result = root_dir
-            .basic_mv(
-                &["movies".into(), "ghibli".into()],
-                &["file.txt".into()],
-                Utc::now(),
-                &store,
-            )
-            .await;
+        let OpResult { result, .. } = Rc::clone(&root_dir)
+            .stat(&["a".into()], &mut store)
+            .await
+            .unwrap();
 
-        assert!(result.is_err());
+        assert_eq!(root_dir.metadata.get_size(), Some(10));
+        assert_eq!(root_dir.metadata.get_descendants(), Some(5));
+        assert_eq!(result.size, Some(8));
     }
 }
@@ -1,6 +1,6 @@
 //! Public fs file node.
 
-use std::rc::Rc;
+use std::{collections::BTreeSet, rc::Rc};
 
 use anyhow::Result;
 
@@ -23,11 +23,12 @@ use crate::{BlockStore, Id, Metadata, UnixFsNodeKind};
 ///
 /// println!("id = {}", file.get_id());
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PublicFile {
     pub(crate) metadata: Metadata,
     pub(crate) userland: Cid,
-    pub(crate) previous: Option<Cid>,
+    #[serde(deserialize_with = "super::previous::deserialize")]
+    pub(crate) previous: BTreeSet<Cid>,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -52,13 +53,13 @@ impl PublicFile {
         Self {
             metadata: Metadata::new(time, UnixFsNodeKind::File),
             userland,
-            previous: None,
+            previous: BTreeSet::new(),
         }
     }
 
-    // Gets the previous value of the file.
-    pub fn get_previous(self: &Rc<Self>) -> Option<Cid> {
-        self.previous
+    /// Gets the CIDs of the revisions this file was derived from.
+    pub fn get_previous(self: &Rc<Self>) -> &BTreeSet<Cid> {
+        &self.previous
     }
 
     /// Stores file in provided block store.
@@ -0,0 +1,469 @@
+//! Glob queries over a public file tree.
+
+use std::{collections::BTreeSet, rc::Rc, str::FromStr};
+
+use anyhow::Result;
+use async_stream::try_stream;
+use futures::Stream;
+
+use crate::{error, BlockStore, FsError};
+
+use super::{PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A glob pattern over slash-separated paths.
+///
+/// Each segment of the pattern is matched against a single path segment and supports:
+///
+/// - `*` which matches any sequence of characters.
+/// - `?` which matches any single character.
+/// - `[abc]`, `[a-z]` and `[!a-z]` (or `[^a-z]`) which match a single character from a class.
+/// - `\` which makes the next character match literally.
+///
+/// A segment that is exactly `**` matches zero or more whole path segments.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::public::GlobPattern;
+///
+/// let pattern: GlobPattern = "pictures/**/*.png".parse().unwrap();
+///
+/// assert!(pattern.matches(&["pictures".into(), "cats".into(), "tabby.png".into()]));
+/// assert!(!pattern.matches(&["pictures".into(), "cats".into(), "tabby.jpg".into()]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobPattern {
+    segments: Vec<GlobSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobSegment {
+    AnyPath,
+    Tokens(Vec<GlobToken>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobToken {
+    Literal(char),
+    AnyChar,
+    AnySequence,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// The positions in a pattern's segments that a path has reached.
+type Positions = BTreeSet<usize>;
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl GlobPattern {
+    /// Checks if a whole path matches the pattern.
+    pub fn matches(&self, path_segments: &[String]) -> bool {
+        let positions = path_segments.iter().fold(self.start(), |positions, name| {
+            self.advance(&positions, name)
+        });
+
+        self.is_match(&positions)
+    }
+
+    /// Gets the positions reached before any path segment is consumed.
+    fn start(&self) -> Positions {
+        self.close(BTreeSet::from([0]))
+    }
+
+    /// Gets the positions reached after consuming a path segment from the given positions.
+    fn advance(&self, positions: &Positions, name: &str) -> Positions {
+        let mut next = BTreeSet::new();
+        for &position in positions {
+            match self.segments.get(position) {
+                Some(GlobSegment::AnyPath) => {
+                    next.insert(position);
+                }
+                Some(GlobSegment::Tokens(tokens)) if utils::matches_tokens(tokens, name) => {
+                    next.insert(position + 1);
+                }
+                _ => (),
+            }
+        }
+
+        self.close(next)
+    }
+
+    /// Adds the positions reachable by letting `**` segments match nothing.
+    fn close(&self, mut positions: Positions) -> Positions {
+        let mut pending = positions.iter().copied().collect::<Vec<_>>();
+        while let Some(position) = pending.pop() {
+            if let Some(GlobSegment::AnyPath) = self.segments.get(position) {
+                if positions.insert(position + 1) {
+                    pending.push(position + 1);
+                }
+            }
+        }
+
+        positions
+    }
+
+    /// Checks if the whole pattern has been consumed.
+    fn is_match(&self, positions: &Positions) -> bool {
+        positions.contains(&self.segments.len())
+    }
+
+    /// Checks if more path segments could still lead to a match.
+    fn can_continue(&self, positions: &Positions) -> bool {
+        positions
+            .iter()
+            .any(|&position| position < self.segments.len())
+    }
+}
+
+impl FromStr for GlobPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment {
+                "**" => Ok(GlobSegment::AnyPath),
+                _ => utils::parse_tokens(segment).map(GlobSegment::Tokens),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if segments.is_empty() {
+            return error(FsError::InvalidGlobPattern(pattern.to_string()));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl GlobToken {
+    fn matches_char(&self, c: char) -> bool {
+        match self {
+            GlobToken::Literal(literal) => *literal == c,
+            GlobToken::AnyChar => true,
+            GlobToken::AnySequence => false,
+            GlobToken::Class { negated, ranges } => {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&c))
+                    != *negated
+            }
+        }
+    }
+}
+
+impl PublicDirectory {
+    /// Finds every node under this directory whose path matches a glob pattern.
+    ///
+    /// See [`GlobPattern`] for the supported syntax. The pattern is matched against directory entry
+    /// names one level at a time, so subtrees that cannot contain a match are never loaded from the
+    /// store. Matches are returned in depth-first order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    /// use futures_util::pin_mut;
+    /// use async_std::stream::StreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let matches = root_dir.find("pictures/**/*.png", &store);
+    ///
+    ///     pin_mut!(matches);
+    ///
+    ///     while let Some(item) = matches.next().await {
+    ///         let (path, _) = item.unwrap();
+    ///         println!("found {}", path.join("/"));
+    ///     }
+    /// }
+    /// ```
+    pub fn find<'a, B: BlockStore>(
+        self: Rc<Self>,
+        pattern: &str,
+        store: &'a B,
+    ) -> impl Stream<Item = Result<(Vec<String>, PublicNode)>> + 'a {
+        let pattern = pattern.parse::<GlobPattern>();
+
+        try_stream! {
+            let pattern = pattern?;
+
+            let mut pending = vec![(vec![], pattern.start(), PublicNode::Dir(self))];
+            while let Some((path, positions, node)) = pending.pop() {
+                if !path.is_empty() && pattern.is_match(&positions) {
+                    yield (path.clone(), node.clone());
+                }
+
+                let dir = match node {
+                    PublicNode::Dir(dir) if pattern.can_continue(&positions) => dir,
+                    _ => continue,
+                };
+
+                let mut children = vec![];
+                for (name, link) in dir.userland.iter() {
+                    let next = pattern.advance(&positions, name);
+                    if next.is_empty() {
+                        continue;
+                    }
+
+                    let mut child_path = path.clone();
+                    child_path.push(name.clone());
+                    children.push((child_path, next, link.resolve_value(store).await?.clone()));
+                }
+
+                pending.extend(children.into_iter().rev());
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use anyhow::Result;
+
+    use crate::{error, FsError};
+
+    use super::GlobToken;
+
+    pub(super) fn parse_tokens(segment: &str) -> Result<Vec<GlobToken>> {
+        let invalid = || error(FsError::InvalidGlobPattern(segment.to_string()));
+
+        let mut tokens = vec![];
+        let mut chars = segment.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' => GlobToken::AnySequence,
+                '?' => GlobToken::AnyChar,
+                '\\' => match chars.next() {
+                    Some(escaped) => GlobToken::Literal(escaped),
+                    None => return invalid(),
+                },
+                '[' => {
+                    let negated = matches!(chars.peek(), Some('!' | '^'));
+                    if negated {
+                        chars.next();
+                    }
+
+                    let mut ranges = vec![];
+                    let mut closed = false;
+                    while let Some(start) = chars.next() {
+                        if start == ']' && !ranges.is_empty() {
+                            closed = true;
+                            break;
+                        }
+
+                        let mut lookahead = chars.clone();
+                        match (lookahead.next(), lookahead.next()) {
+                            (Some('-'), Some(end)) if end != ']' => {
+                                if end < start {
+                                    return invalid();
+                                }
+
+                                chars.next();
+                                chars.next();
+                                ranges.push((start, end));
+                            }
+                            _ => ranges.push((start, start)),
+                        }
+                    }
+
+                    if !closed {
+                        return invalid();
+                    }
+
+                    GlobToken::Class { negated, ranges }
+                }
+                c => GlobToken::Literal(c),
+            };
+
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    pub(super) fn matches_tokens(tokens: &[GlobToken], name: &str) -> bool {
+        let name = name.chars().collect::<Vec<_>>();
+        let (mut t, mut n) = (0, 0);
+        let mut backtrack = None;
+
+        while n < name.len() {
+            match tokens.get(t) {
+                Some(GlobToken::AnySequence) => {
+                    backtrack = Some((t, n));
+                    t += 1;
+                    continue;
+                }
+                Some(token) if token.matches_char(name[n]) => {
+                    t += 1;
+                    n += 1;
+                    continue;
+                }
+                _ => (),
+            }
+
+            match backtrack {
+                Some((star_t, star_n)) => {
+                    backtrack = Some((star_t, star_n + 1));
+                    t = star_t + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            }
+        }
+
+        tokens[t..]
+            .iter()
+            .all(|token| matches!(token, GlobToken::AnySequence))
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod find_tests {
+    use super::*;
+    use crate::{public::OpResult, MemoryBlockStore};
+    use chrono::Utc;
+    use futures::{pin_mut, StreamExt};
+    use libipld::Cid;
+
+    fn path(path: &str) -> Vec<String> {
+        path.split('/').map(String::from).collect()
+    }
+
+    async fn find_paths<B: BlockStore>(
+        root_dir: Rc<PublicDirectory>,
+        pattern: &str,
+        store: &B,
+    ) -> Result<Vec<String>> {
+        let matches = root_dir.find(pattern, store);
+        pin_mut!(matches);
+
+        let mut paths = vec![];
+        while let Some(item) = matches.next().await {
+            paths.push(item?.0.join("/"));
+        }
+
+        Ok(paths)
+    }
+
+    #[test]
+    fn glob_segments_support_wildcards_and_classes() {
+        let cases = [
+            ("*.png", "tabby.png", true),
+            ("*.png", "tabby.jpg", false),
+            ("t?bby.*", "tabby.png", true),
+            ("t?bby.*", "tbby.png", false),
+            ("[a-c]at", "bat", true),
+            ("[!a-c]at", "bat", false),
+            ("[^a-c]at", "rat", true),
+            ("[]]", "]", true),
+            ("a\\*", "a*", true),
+            ("a\\*", "ab", false),
+            ("*a*b*", "xaybz", true),
+        ];
+
+        for (pattern, name, expected) in cases {
+            let glob: GlobPattern = pattern.parse().unwrap();
+            assert_eq!(glob.matches(&[name.into()]), expected, "{pattern}");
+        }
+
+        assert!("[a-".parse::<GlobPattern>().is_err());
+        assert!("".parse::<GlobPattern>().is_err());
+    }
+
+    #[test]
+    fn any_path_segment_matches_zero_or_more_segments() {
+        let pattern: GlobPattern = "pictures/**/*.png".parse().unwrap();
+
+        assert!(pattern.matches(&path("pictures/tabby.png")));
+        assert!(pattern.matches(&path("pictures/cats/orange/tabby.png")));
+        assert!(!pattern.matches(&path("videos/tabby.png")));
+        assert!(!pattern.matches(&path("pictures")));
+    }
+
+    #[async_std::test]
+    async fn find_returns_matching_nodes_without_loading_other_subtrees() {
+        let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
+
+        let mut root_dir = Rc::new(PublicDirectory::new(time));
+        for file in [
+            "pictures/cats/tabby.png",
+            "pictures/cats/notes.txt",
+            "pictures/dogs/puppy.png",
+            "pictures/cover.png",
+            "videos/cats/tabby.png",
+        ] {
+            let OpResult {
+                root_dir: new_root, ..
+            } = root_dir
+                .write(&path(file), Cid::default(), time, &store)
+                .await
+                .unwrap();
+
+            root_dir = new_root;
+        }
+
+        let paths = find_paths(Rc::clone(&root_dir), "pictures/**/*.png", &store)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            paths,
+            [
+                "pictures/cats/tabby.png",
+                "pictures/cover.png",
+                "pictures/dogs/puppy.png"
+            ]
+        );
+
+        // Only the root is decoded, so subtrees stay unresolved until a query needs them.
+        let cid = root_dir.store(&mut store).await.unwrap();
+        let root_dir: Rc<PublicDirectory> = Rc::new(store.get_deserializable(&cid).await.unwrap());
+
+        let paths = find_paths(Rc::clone(&root_dir), "pictures/*", &store)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            paths,
+            ["pictures/cats", "pictures/cover.png", "pictures/dogs"]
+        );
+        assert!(!root_dir.userland["videos"].has_value());
+
+        let paths = find_paths(root_dir, "*/c?ts", &store).await.unwrap();
+
+        assert_eq!(paths, ["pictures/cats", "videos/cats"]);
+    }
+}
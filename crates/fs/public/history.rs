@@ -0,0 +1,200 @@
+//! History of public paths.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    rc::Rc,
+};
+
+use anyhow::Result;
+use async_stream::try_stream;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use libipld::Cid;
+
+use crate::BlockStore;
+
+use super::{PathNodesResult, PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicDirectory {
+    /// Gets a stream of the revisions of the node at the given path, starting with the current one.
+    ///
+    /// This directory and the revisions reachable through its `previous` links are treated as the revisions of
+    /// a root directory, and are visited breadth-first. Each item is the CID of the node, the node and the time
+    /// it was last modified. Revisions in which the path doesn't exist are skipped, and each distinct node is
+    /// only yielded once, even if it appears in several revisions. A symlink at the end of the path is not
+    /// followed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    /// use futures::StreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let base = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::clone(&base)
+    ///         .write_bytes(&["hello.txt".into()], b"hello", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .base_history_on(base, &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let path = ["hello.txt".into()];
+    ///     let revisions = root_dir
+    ///         .history(&path, &mut store)
+    ///         .collect::<Vec<_>>()
+    ///         .await;
+    ///
+    ///     assert_eq!(revisions.len(), 1);
+    /// }
+    /// ```
+    pub fn history<'a, B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &'a [String],
+        store: &'a mut B,
+    ) -> impl Stream<Item = Result<(Cid, PublicNode, DateTime<Utc>)>> + 'a {
+        try_stream! {
+            let mut visited = BTreeSet::new();
+            let mut yielded = BTreeSet::new();
+            let mut pending = VecDeque::from([self]);
+
+            while let Some(root_dir) = pending.pop_front() {
+                if let Some((cid, node)) =
+                    utils::get_node_with_cid(Rc::clone(&root_dir), path_segments, store).await?
+                {
+                    if yielded.insert(cid) {
+                        let modified = node.get_metadata().get_modified();
+                        yield (cid, node, modified);
+                    }
+                }
+
+                for cid in root_dir.get_previous() {
+                    if visited.insert(*cid) {
+                        pending.push_back(Rc::new(store.get_deserializable(cid).await?));
+                    }
+                }
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use super::*;
+
+    /// Gets the node at a path along with its CID, storing the node if it hasn't been stored yet.
+    pub(super) async fn get_node_with_cid<B: BlockStore>(
+        root_dir: Rc<PublicDirectory>,
+        path_segments: &[String],
+        store: &mut B,
+    ) -> Result<Option<(Cid, PublicNode)>> {
+        let (path, name) = match path_segments.split_last() {
+            Some((name, path)) => (path, name),
+            None => {
+                let cid = root_dir.store(store).await?;
+                return Ok(Some((cid, PublicNode::Dir(root_dir))));
+            }
+        };
+
+        let dir = match root_dir.get_path_nodes(path, store).await? {
+            PathNodesResult::Complete(path_nodes) => path_nodes.tail,
+            _ => return Ok(None),
+        };
+
+        match dir.userland.get(name) {
+            Some(link) => {
+                let cid = *link.resolve_cid(store).await?;
+                let node = link.resolve_value(store).await?.clone();
+                Ok(Some((cid, node)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod history_tests {
+    use chrono::{Duration, TimeZone};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{public::OpResult, MemoryBlockStore};
+
+    #[async_std::test]
+    async fn history_yields_each_revision_of_a_path_once() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let path = ["docs".into(), "notes.txt".into()];
+
+        // The file doesn't exist in the first revision.
+        let r0 = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir: r1, .. } = Rc::clone(&r0)
+            .write_bytes(&path, b"first", time + Duration::seconds(1), &mut store)
+            .await
+            .unwrap();
+        let OpResult { root_dir: r1, .. } = r1.base_history_on(r0, &mut store).await.unwrap();
+
+        let OpResult { root_dir: r2, .. } = Rc::clone(&r1)
+            .write_bytes(&path, b"second", time + Duration::seconds(2), &mut store)
+            .await
+            .unwrap();
+        let OpResult { root_dir: r2, .. } = r2.base_history_on(r1, &mut store).await.unwrap();
+
+        // The file is unchanged in the last revision.
+        let OpResult { root_dir: r3, .. } = Rc::clone(&r2)
+            .mkdir(&["pictures".into()], time + Duration::seconds(3), &store)
+            .await
+            .unwrap();
+        let OpResult { root_dir: r3, .. } = r3.base_history_on(r2, &mut store).await.unwrap();
+
+        let revisions = Rc::clone(&r3)
+            .history(&path, &mut store)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let times = revisions
+            .iter()
+            .map(|(_, _, modified)| *modified)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            times,
+            [time + Duration::seconds(2), time + Duration::seconds(1)]
+        );
+
+        let (cid, node, _) = &revisions[1];
+        let file = store.get_deserializable::<PublicNode>(cid).await.unwrap();
+
+        assert_eq!(&file, node);
+        assert_eq!(node.get_metadata().get_size(), Some(5));
+
+        let root_revisions = r3
+            .history(&[], &mut store)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(root_revisions.len(), 4);
+    }
+}
@@ -0,0 +1,515 @@
+//! Journals of the operations applied to public trees.
+
+use std::rc::Rc;
+
+use anyhow::{ensure, Result};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use libipld::Cid;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BlockStore, FsError};
+
+use super::{OpResult, PublicDirectory};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// An operation on a public tree, with the arguments needed to apply it again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Writes a file with the given content, like [`PublicDirectory::write`].
+    Write { path: Vec<String>, content: Cid },
+    /// Creates a directory, like [`PublicDirectory::mkdir`].
+    Mkdir { path: Vec<String> },
+    /// Removes a file or directory, like [`PublicDirectory::rm_at`].
+    Rm { path: Vec<String> },
+    /// Moves a file or directory, like [`PublicDirectory::basic_mv`].
+    BasicMv { from: Vec<String>, to: Vec<String> },
+}
+
+/// An operation recorded in the journal of a root directory.
+///
+/// Entries are stored as IPLD blocks. Each one links to the entry recorded before it, and the root directory
+/// links to the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// The operation that was applied.
+    pub operation: Operation,
+    /// The time the operation was applied with.
+    pub time: DateTime<Utc>,
+    /// The CID of the root directory the operation produced, before the entry was linked from it.
+    pub root: Cid,
+    /// The CID of the entry recorded before this one.
+    pub previous: Option<Cid>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalEntrySerde {
+    operation: Operation,
+    time: i64,
+    time_nanos: u32,
+    root: Cid,
+    previous: Option<Cid>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl JournalEntry {
+    /// Stores the entry in the block store.
+    #[inline(always)]
+    pub async fn store<B: BlockStore>(&self, store: &mut B) -> Result<Cid> {
+        store.put_serializable(self).await
+    }
+}
+
+impl Serialize for JournalEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        JournalEntrySerde {
+            operation: self.operation.clone(),
+            time: self.time.timestamp(),
+            time_nanos: self.time.timestamp_subsec_nanos(),
+            root: self.root,
+            previous: self.previous,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JournalEntry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let JournalEntrySerde {
+            operation,
+            time,
+            time_nanos,
+            root,
+            previous,
+        } = JournalEntrySerde::deserialize(deserializer)?;
+
+        let time = match NaiveDateTime::from_timestamp_opt(time, time_nanos) {
+            Some(time) => Utc.from_utc_datetime(&time),
+            None => {
+                return Err(de::Error::custom(format!(
+                    "Invalid timestamp: {time}s {time_nanos}ns"
+                )))
+            }
+        };
+
+        Ok(Self {
+            operation,
+            time,
+            root,
+            previous,
+        })
+    }
+}
+
+impl PublicDirectory {
+    /// Gets the CID of the last entry in the journal of this root directory.
+    pub fn get_journal(&self) -> Option<Cid> {
+        self.journal
+    }
+
+    /// Applies an operation to this root directory without recording it.
+    pub async fn apply<B: BlockStore>(
+        self: Rc<Self>,
+        operation: &Operation,
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<Rc<Self>> {
+        Ok(match operation {
+            Operation::Write { path, content } => {
+                self.write(path, *content, time, store).await?.root_dir
+            }
+            Operation::Mkdir { path } => self.mkdir(path, time, store).await?.root_dir,
+            Operation::Rm { path } => self.rm_at(path, time, store).await?.root_dir,
+            Operation::BasicMv { from, to } => self.basic_mv(from, to, time, store).await?.root_dir,
+        })
+    }
+
+    /// Applies an operation to this root directory and records it in the journal.
+    ///
+    /// Journals are optional: a root directory only has one when its last change was recorded with this
+    /// function. Changes made in other ways, including merges, aren't recorded and leave the new root without a
+    /// journal, since it would no longer describe how the tree was built. Revisions such as those made by
+    /// [`PublicDirectory::base_history_on`] keep it. The result is the CID of the new entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{Operation, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let base = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::clone(&base)
+    ///         .apply_and_record(
+    ///             Operation::Mkdir { path: vec!["pictures".into()] },
+    ///             Utc::now(),
+    ///             &mut store,
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let journal = Rc::clone(&root_dir).read_journal(&store).await.unwrap();
+    ///     let OpResult { root_dir: replayed, .. } = base.replay(&journal, &mut store).await.unwrap();
+    ///
+    ///     assert_eq!(
+    ///         replayed.store(&mut store).await.unwrap(),
+    ///         root_dir.store(&mut store).await.unwrap()
+    ///     );
+    /// }
+    /// ```
+    pub async fn apply_and_record<B: BlockStore>(
+        self: Rc<Self>,
+        operation: Operation,
+        time: DateTime<Utc>,
+        store: &mut B,
+    ) -> Result<OpResult<Cid>> {
+        let previous = self.journal;
+        let root_dir = self.apply(&operation, time, store).await?;
+
+        let entry = JournalEntry {
+            operation,
+            time,
+            root: root_dir.store(store).await?,
+            previous,
+        };
+
+        Self::link_journal(root_dir, &entry, store).await
+    }
+
+    /// Stores a journal entry and links it from the root directory it was recorded for.
+    async fn link_journal<B: BlockStore>(
+        root_dir: Rc<Self>,
+        entry: &JournalEntry,
+        store: &mut B,
+    ) -> Result<OpResult<Cid>> {
+        let entry_cid = entry.store(store).await?;
+        let mut root_dir = (*root_dir).clone();
+        root_dir.journal = Some(entry_cid);
+
+        Ok(OpResult {
+            root_dir: Rc::new(root_dir),
+            result: entry_cid,
+        })
+    }
+
+    /// Gets the entries of the journal of this root directory, oldest first.
+    pub async fn read_journal<B: BlockStore>(
+        self: Rc<Self>,
+        store: &B,
+    ) -> Result<Vec<JournalEntry>> {
+        self.read_journal_until(None, store).await
+    }
+
+    /// Gets the entries of the journal of this root directory that were recorded after `base`, oldest first.
+    ///
+    /// `base` must be a revision this root directory was built from, like the root a local tree diverged from.
+    /// The entries returned are the local operations to pass to [`PublicDirectory::rebase`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{Operation, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let mut root_dir = Rc::new(PublicDirectory::new(Utc::now()));
+    ///     let mut revisions = vec![];
+    ///
+    ///     for name in ["pictures", "music"] {
+    ///         let OpResult { root_dir: new_root, .. } = root_dir
+    ///             .apply_and_record(Operation::Mkdir { path: vec![name.into()] }, Utc::now(), &mut store)
+    ///             .await
+    ///             .unwrap();
+    ///
+    ///         revisions.push(Rc::clone(&new_root));
+    ///         root_dir = new_root;
+    ///     }
+    ///
+    ///     let entries = root_dir.read_journal_since(&revisions[0], &store).await.unwrap();
+    ///
+    ///     assert_eq!(entries.len(), 1);
+    ///     assert_eq!(entries[0].operation, Operation::Mkdir { path: vec!["music".into()] });
+    /// }
+    /// ```
+    pub async fn read_journal_since<B: BlockStore>(
+        self: Rc<Self>,
+        base: &Self,
+        store: &B,
+    ) -> Result<Vec<JournalEntry>> {
+        // The journal of the base is a prefix of ours, unless ours was dropped and restarted since.
+        self.read_journal_until(base.journal, store).await
+    }
+
+    /// Reads journal entries from the last one back to the entry `stop`, not included.
+    async fn read_journal_until<B: BlockStore>(
+        &self,
+        stop: Option<Cid>,
+        store: &B,
+    ) -> Result<Vec<JournalEntry>> {
+        let mut entries = vec![];
+        let mut next = self.journal;
+
+        while let Some(cid) = next.filter(|cid| Some(*cid) != stop) {
+            let entry = store.get_deserializable::<JournalEntry>(&cid).await?;
+            next = entry.previous;
+            entries.push(entry);
+        }
+
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Rebuilds a tree by applying the operations of journal entries to this root directory, in order and with
+    /// their recorded times.
+    ///
+    /// This must be the root directory the entries were first applied to. Each rebuilt root is checked against
+    /// the one the entry recorded, and the replay fails with [`FsError::JournalMismatch`] once they differ.
+    /// The operations are recorded in the journal of the new tree, so the rebuilt tree is the same as the one
+    /// that recorded them. To apply the operations to another tree, use [`PublicDirectory::rebase`].
+    pub async fn replay<B: BlockStore>(
+        self: Rc<Self>,
+        entries: &[JournalEntry],
+        store: &mut B,
+    ) -> Result<OpResult<()>> {
+        let mut root_dir = self;
+        for entry in entries {
+            let previous = root_dir.journal;
+            let applied = root_dir.apply(&entry.operation, entry.time, store).await?;
+            ensure!(
+                applied.store(store).await? == entry.root,
+                FsError::JournalMismatch
+            );
+
+            let entry = JournalEntry {
+                previous,
+                ..entry.clone()
+            };
+
+            root_dir = Self::link_journal(applied, &entry, store).await?.root_dir;
+        }
+
+        Ok(OpResult {
+            root_dir,
+            result: (),
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod journal_tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        dagcbor,
+        public::{content, MergePolicy},
+        MemoryBlockStore,
+    };
+
+    #[async_std::test]
+    async fn recorded_operations_can_be_replayed() {
+        let time = Utc.timestamp(1_650_000_000, 500);
+        let mut store = MemoryBlockStore::default();
+        let base = Rc::new(PublicDirectory::new(time));
+        let content = content::put_bytes(b"hello", &mut store).await.unwrap();
+
+        let operations = [
+            Operation::Mkdir {
+                path: vec!["docs".into()],
+            },
+            Operation::Write {
+                path: vec!["docs".into(), "draft.md".into()],
+                content,
+            },
+            Operation::BasicMv {
+                from: vec!["docs".into(), "draft.md".into()],
+                to: vec!["docs".into(), "report.md".into()],
+            },
+            Operation::Write {
+                path: vec!["todo.txt".into()],
+                content,
+            },
+            Operation::Rm {
+                path: vec!["todo.txt".into()],
+            },
+        ];
+
+        let mut root_dir = Rc::clone(&base);
+        for (index, operation) in operations.iter().enumerate() {
+            root_dir = root_dir
+                .apply_and_record(
+                    operation.clone(),
+                    time + Duration::seconds(index as i64),
+                    &mut store,
+                )
+                .await
+                .unwrap()
+                .root_dir;
+        }
+
+        let journal = Rc::clone(&root_dir).read_journal(&store).await.unwrap();
+        let recorded = journal
+            .iter()
+            .map(|entry| entry.operation.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(recorded, operations);
+        assert_eq!(journal[1].time, time + Duration::seconds(1));
+        assert_eq!(journal[0].previous, None);
+
+        let OpResult {
+            root_dir: replayed, ..
+        } = Rc::clone(&base).replay(&journal, &mut store).await.unwrap();
+
+        assert_eq!(
+            replayed.store(&mut store).await.unwrap(),
+            root_dir.store(&mut store).await.unwrap()
+        );
+
+        let OpResult {
+            root_dir: partial, ..
+        } = base.replay(&journal[..2], &mut store).await.unwrap();
+
+        assert_eq!(partial.get_journal(), journal[2].previous);
+    }
+
+    #[async_std::test]
+    async fn changes_that_arent_recorded_drop_the_journal() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let content = content::put_bytes(b"hello", &mut store).await.unwrap();
+        let base = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = Rc::clone(&base)
+            .apply_and_record(
+                Operation::Mkdir {
+                    path: vec!["docs".into()],
+                },
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        assert!(root_dir.get_journal().is_some());
+
+        let written = Rc::clone(&root_dir)
+            .write(&["todo.txt".into()], content, time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        assert_eq!(written.get_journal(), None);
+
+        let removed = Rc::clone(&root_dir)
+            .rm(&["docs".into()], &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        assert_eq!(removed.get_journal(), None);
+
+        let OpResult {
+            root_dir: merged, ..
+        } = PublicDirectory::merge(
+            root_dir,
+            written,
+            base,
+            &MergePolicy::LastWriterWins,
+            &mut store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(merged.get_journal(), None);
+    }
+
+    #[async_std::test]
+    async fn replay_fails_when_a_rebuilt_root_differs_from_the_recorded_one() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let base = Rc::new(PublicDirectory::new(time));
+
+        let OpResult { root_dir, .. } = Rc::clone(&base)
+            .apply_and_record(
+                Operation::Mkdir {
+                    path: vec!["docs".into()],
+                },
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        let journal = root_dir.read_journal(&store).await.unwrap();
+        let other_base = base
+            .mkdir(&["music".into()], time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let error = other_base.replay(&journal, &mut store).await.unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::JournalMismatch)
+        );
+    }
+
+    #[test]
+    fn journal_entries_can_be_encoded_and_decoded() {
+        let entry = JournalEntry {
+            operation: Operation::BasicMv {
+                from: vec!["a".into()],
+                to: vec!["b".into()],
+            },
+            time: Utc.timestamp(1_650_000_000, 123),
+            root: Cid::default(),
+            previous: Some(Cid::default()),
+        };
+
+        let encoded = dagcbor::encode(&entry).unwrap();
+        let decoded: JournalEntry = dagcbor::decode(&encoded).unwrap();
+
+        assert_eq!(decoded, entry);
+    }
+
+    #[test]
+    fn journal_entries_with_invalid_times_are_rejected() {
+        let entry = JournalEntrySerde {
+            operation: Operation::Mkdir {
+                path: vec!["a".into()],
+            },
+            time: i64::MAX,
+            time_nanos: 0,
+            root: Cid::default(),
+            previous: None,
+        };
+
+        let encoded = dagcbor::encode(&entry).unwrap();
+        assert!(dagcbor::decode::<JournalEntry>(&encoded).is_err());
+    }
+}
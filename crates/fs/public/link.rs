@@ -2,7 +2,7 @@
 
 use std::rc::Rc;
 
-use super::{PublicDirectory, PublicFile, PublicNode};
+use super::{PublicDirectory, PublicFile, PublicNode, PublicSymlink};
 use crate::Link;
 
 //--------------------------------------------------------------------------------------------------
@@ -33,4 +33,9 @@ impl PublicLink {
     pub fn with_file(file: Rc<PublicFile>) -> Self {
         Link::from(PublicNode::File(file))
     }
+
+    /// Creates a new symlink node link.
+    pub fn with_symlink(symlink: Rc<PublicSymlink>) -> Self {
+        Link::from(PublicNode::Symlink(symlink))
+    }
 }
@@ -0,0 +1,523 @@
+//! Three-way merges of public trees.
+
+use std::{collections::BTreeSet, fmt, rc::Rc};
+
+use anyhow::Result;
+use async_recursion::async_recursion;
+
+use crate::BlockStore;
+
+use super::{OpResult, PublicDirectory, PublicLink, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A path that was changed differently in both trees being merged.
+///
+/// A side is `None` if the path was removed in it. Paths that are directories in both trees are merged
+/// entry by entry instead of conflicting.
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    /// The path that conflicts.
+    pub path: Vec<String>,
+    /// The node at the path in our tree.
+    pub ours: Option<PublicNode>,
+    /// The node at the path in their tree.
+    pub theirs: Option<PublicNode>,
+}
+
+/// How a [`MergeConflict`] is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeResolution {
+    /// Keeps our side.
+    Ours,
+    /// Keeps their side.
+    Theirs,
+    /// Keeps our side at the path and their side under a renamed copy next to it.
+    Both,
+}
+
+/// A function that decides how a conflict is resolved.
+pub type MergeCallback = Box<dyn Fn(&MergeConflict) -> MergeResolution>;
+
+/// How [`PublicDirectory::merge`] resolves conflicts.
+pub enum MergePolicy {
+    /// Keeps the side that was modified last, or our side if both were modified at the same time. A side that
+    /// still has the path wins over a side that removed it.
+    LastWriterWins,
+    /// Keeps both sides, renaming their copy to `<name> (theirs)`.
+    KeepBoth,
+    /// Lets a function decide.
+    Callback(MergeCallback),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl MergePolicy {
+    /// Decides how a conflict is resolved.
+    pub fn resolve(&self, conflict: &MergeConflict) -> MergeResolution {
+        match self {
+            Self::LastWriterWins => match (&conflict.ours, &conflict.theirs) {
+                (Some(ours), Some(theirs))
+                    if theirs.get_metadata().get_modified()
+                        > ours.get_metadata().get_modified() =>
+                {
+                    MergeResolution::Theirs
+                }
+                (None, Some(_)) => MergeResolution::Theirs,
+                _ => MergeResolution::Ours,
+            },
+            Self::KeepBoth => MergeResolution::Both,
+            Self::Callback(callback) => callback(conflict),
+        }
+    }
+}
+
+impl fmt::Debug for MergePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LastWriterWins => write!(f, "LastWriterWins"),
+            Self::KeepBoth => write!(f, "KeepBoth"),
+            Self::Callback(_) => write!(f, "Callback(..)"),
+        }
+    }
+}
+
+impl PublicDirectory {
+    /// Merges two trees that diverged from a common base.
+    ///
+    /// Changes made on only one side are kept. Paths changed differently on both sides are resolved with
+    /// `policy`, and the resulting conflicts are returned. Entries are compared by CID, so subtrees that are
+    /// the same on two sides are not loaded. The merged root keeps our metadata, except for the modified time,
+    /// which is the later one of the two roots. Its `previous` links point to both roots. It has no journal, since a merge can't be replayed; the journals of both sides can still
+    /// be read from the roots it links to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{MergePolicy, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let base = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir: ours, .. } = Rc::clone(&base)
+    ///         .mkdir(&["pictures".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: theirs, .. } = Rc::clone(&base)
+    ///         .mkdir(&["music".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, result } =
+    ///         PublicDirectory::merge(ours, theirs, base, &MergePolicy::LastWriterWins, &mut store)
+    ///             .await
+    ///             .unwrap();
+    ///
+    ///     assert!(result.is_empty());
+    ///     assert_eq!(root_dir.ls(&[], &store).await.unwrap().result.len(), 2);
+    /// }
+    /// ```
+    pub async fn merge<B: BlockStore>(
+        ours: Rc<Self>,
+        theirs: Rc<Self>,
+        base: Rc<Self>,
+        policy: &MergePolicy,
+        store: &mut B,
+    ) -> Result<OpResult<Vec<MergeConflict>>> {
+        let mut conflicts = vec![];
+        let mut merged = Self::merge_helper(
+            &[],
+            &ours,
+            &theirs,
+            Some(&base),
+            policy,
+            &mut conflicts,
+            store,
+        )
+        .await?;
+
+        merged.previous = BTreeSet::from([ours.store(store).await?, theirs.store(store).await?]);
+        merged.journal = None;
+        merged.metadata.set_modified(
+            ours.metadata
+                .get_modified()
+                .max(theirs.metadata.get_modified()),
+        );
+
+        Ok(OpResult {
+            root_dir: Rc::new(merged).complete_usage(store).await?,
+            result: conflicts,
+        })
+    }
+
+    /// Merges the entries of two directories with an optional common base.
+    #[async_recursion(?Send)]
+    async fn merge_helper<B>(
+        path: &[String],
+        ours: &Rc<Self>,
+        theirs: &Rc<Self>,
+        base: Option<&'async_recursion Rc<Self>>,
+        policy: &MergePolicy,
+        conflicts: &mut Vec<MergeConflict>,
+        store: &mut B,
+    ) -> Result<Self>
+    where
+        B: BlockStore,
+    {
+        let mut merged = (**ours).clone();
+
+        let mut names = ours
+            .userland
+            .keys()
+            .chain(theirs.userland.keys())
+            .chain(base.iter().flat_map(|base| base.userland.keys()))
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let our_link = ours.userland.get(&name);
+            let their_link = theirs.userland.get(&name);
+            let base_link = base.and_then(|base| base.userland.get(&name));
+
+            if utils::same(our_link, their_link, store).await?
+                || utils::same(base_link, their_link, store).await?
+            {
+                continue;
+            }
+
+            if utils::same(base_link, our_link, store).await? {
+                merged
+                    .replace_entry(&name, their_link.cloned(), store)
+                    .await?;
+                continue;
+            }
+
+            let our_node = utils::resolve(our_link, store).await?;
+            let their_node = utils::resolve(their_link, store).await?;
+            let mut entry_path = path.to_vec();
+            entry_path.push(name.clone());
+
+            if let (Some(PublicNode::Dir(our_dir)), Some(PublicNode::Dir(their_dir))) =
+                (&our_node, &their_node)
+            {
+                let base_dir = match utils::resolve(base_link, store).await? {
+                    Some(PublicNode::Dir(base_dir)) => Some(base_dir),
+                    _ => None,
+                };
+
+                let merged_dir = Self::merge_helper(
+                    &entry_path,
+                    our_dir,
+                    their_dir,
+                    base_dir.as_ref(),
+                    policy,
+                    conflicts,
+                    store,
+                )
+                .await?;
+
+                let link = PublicLink::with_dir(Rc::new(merged_dir));
+                merged.replace_entry(&name, Some(link), store).await?;
+                continue;
+            }
+
+            let conflict = MergeConflict {
+                path: entry_path,
+                ours: our_node,
+                theirs: their_node,
+            };
+
+            match policy.resolve(&conflict) {
+                MergeResolution::Ours => (),
+                MergeResolution::Theirs => {
+                    merged
+                        .replace_entry(&name, their_link.cloned(), store)
+                        .await?;
+                }
+                MergeResolution::Both => {
+                    if let Some(their_link) = their_link {
+                        let copy_name = match our_link {
+                            Some(_) => utils::copy_name(&merged, &name),
+                            None => name.clone(),
+                        };
+
+                        merged
+                            .replace_entry(&copy_name, Some(their_link.clone()), store)
+                            .await?;
+                    }
+                }
+            }
+
+            conflicts.push(conflict);
+        }
+
+        Ok(merged)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use anyhow::Result;
+
+    use crate::BlockStore;
+
+    use super::{PublicDirectory, PublicLink, PublicNode};
+
+    /// Checks if two optional entries are both missing or have the same CID.
+    pub(super) async fn same<B: BlockStore>(
+        link: Option<&PublicLink>,
+        other: Option<&PublicLink>,
+        store: &mut B,
+    ) -> Result<bool> {
+        Ok(match (link, other) {
+            (Some(link), Some(other)) => link.deep_eq(other, store).await?,
+            (None, None) => true,
+            _ => false,
+        })
+    }
+
+    /// Resolves an optional entry.
+    pub(super) async fn resolve<B: BlockStore>(
+        link: Option<&PublicLink>,
+        store: &B,
+    ) -> Result<Option<PublicNode>> {
+        Ok(match link {
+            Some(link) => Some(link.resolve_value(store).await?.clone()),
+            None => None,
+        })
+    }
+
+    /// Gets a name for their copy of an entry that isn't taken in the directory.
+    pub(super) fn copy_name(dir: &PublicDirectory, name: &str) -> String {
+        let mut copy_name = format!("{name} (theirs)");
+        let mut count = 1;
+
+        while dir.userland.contains_key(&copy_name) {
+            count += 1;
+            copy_name = format!("{name} (theirs {count})");
+        }
+
+        copy_name
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod merge_tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{MemoryBlockStore, WnfsPath};
+
+    /// Creates a base tree and two trees that diverged from it.
+    async fn diverged(store: &mut MemoryBlockStore) -> [Rc<PublicDirectory>; 3] {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut base = Rc::new(PublicDirectory::new(time));
+        for path in ["docs/a.txt", "docs/b.txt", "misc/c.txt"] {
+            base = base
+                .write_bytes(&path.parse::<WnfsPath>().unwrap(), b"base", time, store)
+                .await
+                .unwrap()
+                .root_dir;
+        }
+
+        let ours = Rc::clone(&base)
+            .write_bytes(
+                &"docs/a.txt".parse::<WnfsPath>().unwrap(),
+                b"ours",
+                time + Duration::seconds(1),
+                store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let ours = ours
+            .write_bytes(
+                &"ours.txt".parse::<WnfsPath>().unwrap(),
+                b"ours",
+                time,
+                store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let theirs = Rc::clone(&base)
+            .write_bytes(
+                &"docs/a.txt".parse::<WnfsPath>().unwrap(),
+                b"theirs",
+                time + Duration::seconds(2),
+                store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let theirs = theirs
+            .write_bytes(
+                &"docs/b.txt".parse::<WnfsPath>().unwrap(),
+                b"theirs",
+                time,
+                store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let theirs = theirs
+            .rm(&"misc/c.txt".parse::<WnfsPath>().unwrap(), store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        [base, ours, theirs]
+    }
+
+    async fn read(
+        root_dir: &Rc<PublicDirectory>,
+        path: &str,
+        store: &mut MemoryBlockStore,
+    ) -> Vec<u8> {
+        Rc::clone(root_dir)
+            .read_bytes(&path.parse::<WnfsPath>().unwrap(), store)
+            .await
+            .unwrap()
+            .result
+    }
+
+    #[async_std::test]
+    async fn merge_keeps_changes_from_both_sides() {
+        let mut store = MemoryBlockStore::default();
+        let [base, ours, theirs] = diverged(&mut store).await;
+
+        let OpResult { root_dir, result } = PublicDirectory::merge(
+            Rc::clone(&ours),
+            Rc::clone(&theirs),
+            base,
+            &MergePolicy::LastWriterWins,
+            &mut store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, ["docs", "a.txt"]);
+        assert_eq!(read(&root_dir, "docs/a.txt", &mut store).await, b"theirs");
+        assert_eq!(read(&root_dir, "docs/b.txt", &mut store).await, b"theirs");
+        assert_eq!(read(&root_dir, "ours.txt", &mut store).await, b"ours");
+        assert_eq!(
+            Rc::clone(&root_dir)
+                .ls(&["misc".into()], &store)
+                .await
+                .unwrap()
+                .result
+                .len(),
+            0
+        );
+        assert_eq!(
+            root_dir.previous,
+            BTreeSet::from([
+                ours.store(&mut store).await.unwrap(),
+                theirs.store(&mut store).await.unwrap()
+            ])
+        );
+        assert_eq!(root_dir.metadata.get_size(), Some(16));
+    }
+
+    #[async_std::test]
+    async fn merge_conflicts_follow_the_policy() {
+        let mut store = MemoryBlockStore::default();
+        let [base, ours, theirs] = diverged(&mut store).await;
+
+        let OpResult { root_dir, .. } = PublicDirectory::merge(
+            Rc::clone(&ours),
+            Rc::clone(&theirs),
+            Rc::clone(&base),
+            &MergePolicy::KeepBoth,
+            &mut store,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(read(&root_dir, "docs/a.txt", &mut store).await, b"ours");
+        assert_eq!(
+            read(&root_dir, "docs/a.txt (theirs)", &mut store).await,
+            b"theirs"
+        );
+
+        let policy = MergePolicy::Callback(Box::new(|conflict| {
+            assert!(conflict.ours.is_some() && conflict.theirs.is_some());
+            MergeResolution::Ours
+        }));
+
+        let OpResult { root_dir, .. } =
+            PublicDirectory::merge(ours, theirs, base, &policy, &mut store)
+                .await
+                .unwrap();
+
+        assert_eq!(read(&root_dir, "docs/a.txt", &mut store).await, b"ours");
+    }
+
+    #[async_std::test]
+    async fn merged_history_visits_each_revision_once() {
+        let mut store = MemoryBlockStore::default();
+        let [base, ours, theirs] = diverged(&mut store).await;
+
+        let ours = ours
+            .base_history_on(Rc::clone(&base), &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let theirs = theirs
+            .base_history_on(Rc::clone(&base), &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let OpResult { root_dir, .. } = PublicDirectory::merge(
+            Rc::clone(&ours),
+            Rc::clone(&theirs),
+            Rc::clone(&base),
+            &MergePolicy::LastWriterWins,
+            &mut store,
+        )
+        .await
+        .unwrap();
+
+        let history = root_dir
+            .get_history(&store)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history[..2].iter().collect::<BTreeSet<_>>(),
+            BTreeSet::from([
+                &ours.store(&mut store).await.unwrap(),
+                &theirs.store(&mut store).await.unwrap()
+            ])
+        );
+        assert_eq!(history[2], base.store(&mut store).await.unwrap());
+    }
+}
@@ -1,9 +1,36 @@
+mod compaction;
+pub mod content;
+mod diff;
 mod directory;
 mod file;
+mod find;
+mod history;
+mod journal;
 mod link;
+mod merge;
 mod node;
+mod previous;
+mod rebase;
+mod registry;
+mod restore;
+mod revision;
+mod stat;
+mod symlink;
+mod walk;
+mod xattr;
 
+pub use compaction::*;
+pub use diff::*;
 pub use directory::*;
 pub use file::*;
+pub use find::*;
+pub use journal::*;
 use link::*;
+pub use merge::*;
 pub use node::*;
+pub use rebase::*;
+pub use registry::*;
+pub use revision::*;
+pub use stat::*;
+pub use symlink::*;
+pub use walk::*;
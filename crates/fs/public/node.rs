@@ -1,6 +1,6 @@
 //! Public node system in-memory representation.
 
-use std::rc::Rc;
+use std::{collections::BTreeSet, rc::Rc};
 
 use anyhow::{bail, Result};
 use async_trait::async_trait;
@@ -8,64 +8,159 @@ use chrono::{DateTime, Utc};
 use libipld::{Cid, Ipld};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{PublicDirectory, PublicFile};
+use super::{PublicDirectory, PublicFile, PublicSymlink};
 use crate::{common::BlockStore, AsyncSerialize, FsError, Id, Metadata, UnixFsNodeKind};
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
 //--------------------------------------------------------------------------------------------------
 
-/// A node in a WNFS public file system. This can either be a file or a directory.
+/// A node in a WNFS public file system. This can either be a file, a directory or a symlink.
 ///
 /// PublicNode is serialized as enum.
 #[derive(Debug, Clone)]
 pub enum PublicNode {
     File(Rc<PublicFile>),
     Dir(Rc<PublicDirectory>),
+    Symlink(Rc<PublicSymlink>),
+}
+
+/// What a node adds to the sizes of the directory containing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Usage {
+    /// The content size of the files whose size is known.
+    pub(crate) size: u64,
+    /// The number of nodes.
+    pub(crate) nodes: u64,
+    /// The number of files whose size is unknown.
+    pub(crate) unknown_sizes: u64,
 }
 
 //--------------------------------------------------------------------------------------------------
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
+impl Usage {
+    /// Adds the usage of another node.
+    pub(crate) fn add(self, other: Self) -> Self {
+        Self {
+            size: self.size + other.size,
+            nodes: self.nodes + other.nodes,
+            unknown_sizes: self.unknown_sizes + other.unknown_sizes,
+        }
+    }
+
+    /// Replaces a part of this total. Returns `None` if the part isn't included in the total.
+    pub(crate) fn replace(self, old_part: Self, new_part: Self) -> Option<Self> {
+        Some(Self {
+            size: self.size.checked_sub(old_part.size)? + new_part.size,
+            nodes: self.nodes.checked_sub(old_part.nodes)? + new_part.nodes,
+            unknown_sizes: self.unknown_sizes.checked_sub(old_part.unknown_sizes)?
+                + new_part.unknown_sizes,
+        })
+    }
+}
+
 impl PublicNode {
     /// Creates node with updated modified time.
     pub fn update_mtime(&self, time: DateTime<Utc>) -> Self {
         match self {
             Self::File(file) => {
                 let mut file = (**file).clone();
-                file.metadata.unix_fs.modified = time.timestamp();
+                file.metadata.set_modified(time);
+                Self::File(Rc::new(file))
+            }
+            Self::Dir(dir) => {
+                let mut dir = (**dir).clone();
+                dir.metadata.set_modified(time);
+                Self::Dir(Rc::new(dir))
+            }
+            Self::Symlink(symlink) => {
+                let mut symlink = (**symlink).clone();
+                symlink.metadata.set_modified(time);
+                Self::Symlink(Rc::new(symlink))
+            }
+        }
+    }
+
+    /// Creates node with the given metadata.
+    pub fn update_metadata(&self, metadata: Metadata) -> Self {
+        match self {
+            Self::File(file) => {
+                let mut file = (**file).clone();
+                file.metadata = metadata;
                 Self::File(Rc::new(file))
             }
             Self::Dir(dir) => {
                 let mut dir = (**dir).clone();
-                dir.metadata.unix_fs.modified = time.timestamp();
+                dir.metadata = metadata;
                 Self::Dir(Rc::new(dir))
             }
+            Self::Symlink(symlink) => {
+                let mut symlink = (**symlink).clone();
+                symlink.metadata = metadata;
+                Self::Symlink(Rc::new(symlink))
+            }
         }
     }
 
-    /// Creates node with updated previous pointer value.
-    pub fn update_previous(&self, cid: Option<Cid>) -> Self {
+    /// Creates node with updated previous pointer values.
+    pub fn update_previous(&self, previous: BTreeSet<Cid>) -> Self {
         match self {
             Self::File(file) => {
                 let mut file = (**file).clone();
-                file.previous = cid;
+                file.previous = previous;
                 Self::File(Rc::new(file))
             }
             Self::Dir(dir) => {
                 let mut dir = (**dir).clone();
-                dir.previous = cid;
+                dir.previous = previous;
                 Self::Dir(Rc::new(dir))
             }
+            Self::Symlink(symlink) => {
+                let mut symlink = (**symlink).clone();
+                symlink.previous = previous;
+                Self::Symlink(Rc::new(symlink))
+            }
         }
     }
 
-    /// Gets previous ancestor of a node.
-    pub fn get_previous(&self) -> Option<Cid> {
+    /// Gets the previous ancestors of a node.
+    pub fn get_previous(&self) -> &BTreeSet<Cid> {
         match self {
             Self::File(file) => file.get_previous(),
             Self::Dir(dir) => dir.get_previous(),
+            Self::Symlink(symlink) => symlink.get_previous(),
+        }
+    }
+
+    /// Gets the metadata of a node.
+    pub fn get_metadata(&self) -> &Metadata {
+        match self {
+            Self::File(file) => &file.metadata,
+            Self::Dir(dir) => &dir.metadata,
+            Self::Symlink(symlink) => &symlink.metadata,
+        }
+    }
+
+    /// Gets what this node adds to the sizes of the directory containing it.
+    ///
+    /// Returns `None` for a directory whose sizes haven't been added up yet.
+    pub(crate) fn get_usage(&self) -> Option<Usage> {
+        match self {
+            Self::File(file) => Some(Usage {
+                size: file.metadata.size.unwrap_or_default(),
+                nodes: 1,
+                unknown_sizes: u64::from(file.metadata.size.is_none()),
+            }),
+            Self::Dir(dir) => dir.get_totals().map(|totals| Usage {
+                nodes: totals.nodes + 1,
+                ..totals
+            }),
+            Self::Symlink(_) => Some(Usage {
+                nodes: 1,
+                ..Usage::default()
+            }),
         }
     }
 
@@ -93,12 +188,21 @@ impl PublicNode {
         })
     }
 
+    /// Casts a node to a symlink.
+    pub fn as_symlink(&self) -> Result<Rc<PublicSymlink>> {
+        Ok(match self {
+            Self::Symlink(symlink) => Rc::clone(symlink),
+            _ => bail!(FsError::NotASymlink),
+        })
+    }
+
     /// Stores a WNFS node as block(s) in chosen block store.
     #[inline]
     pub async fn store<B: BlockStore>(&self, store: &mut B) -> Result<Cid> {
         Ok(match self {
             Self::File(file) => file.store(store).await?,
             Self::Dir(dir) => dir.store(store).await?,
+            Self::Symlink(symlink) => symlink.store(store).await?,
         })
     }
 
@@ -107,11 +211,17 @@ impl PublicNode {
         matches!(self, Self::Dir(_))
     }
 
+    /// Returns true if underlying node is a symlink.
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, Self::Symlink(_))
+    }
+
     /// Gets the node kind.
     pub fn kind(&self) -> UnixFsNodeKind {
         match self {
             Self::File(_) => UnixFsNodeKind::File,
             Self::Dir(_) => UnixFsNodeKind::Dir,
+            Self::Symlink(_) => UnixFsNodeKind::SymLink,
         }
     }
 }
@@ -121,6 +231,7 @@ impl Id for PublicNode {
         match self {
             PublicNode::File(file) => file.get_id(),
             PublicNode::Dir(dir) => dir.get_id(),
+            PublicNode::Symlink(symlink) => symlink.get_id(),
         }
     }
 }
@@ -134,6 +245,9 @@ impl PartialEq for PublicNode {
             (Self::Dir(self_dir), Self::Dir(other_dir)) => {
                 Rc::ptr_eq(self_dir, other_dir) || self_dir == other_dir
             }
+            (Self::Symlink(self_symlink), Self::Symlink(other_symlink)) => {
+                Rc::ptr_eq(self_symlink, other_symlink) || self_symlink == other_symlink
+            }
             _ => false,
         }
     }
@@ -151,6 +265,10 @@ impl<'de> Deserialize<'de> for PublicNode {
 impl TryFrom<Ipld> for PublicNode {
     type Error = String;
 
+    /// Decodes a node based on the kind in its metadata.
+    ///
+    /// The metadata is decoded first, so nodes from an unsupported version are rejected before the rest of
+    /// the node is looked at.
     fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Map(map) => {
@@ -163,6 +281,10 @@ impl TryFrom<Ipld> for PublicNode {
                     PublicNode::from(
                         PublicFile::deserialize(Ipld::Map(map)).map_err(|e| e.to_string())?,
                     )
+                } else if metadata.is_symlink() {
+                    PublicNode::from(
+                        PublicSymlink::deserialize(Ipld::Map(map)).map_err(|e| e.to_string())?,
+                    )
                 } else {
                     PublicNode::from(
                         PublicDirectory::deserialize(Ipld::Map(map)).map_err(|e| e.to_string())?,
@@ -186,6 +308,12 @@ impl From<PublicDirectory> for PublicNode {
     }
 }
 
+impl From<PublicSymlink> for PublicNode {
+    fn from(symlink: PublicSymlink) -> Self {
+        Self::Symlink(Rc::new(symlink))
+    }
+}
+
 /// Implements async deserialization for serde serializable types.
 #[async_trait(?Send)]
 impl AsyncSerialize for PublicNode {
@@ -197,6 +325,7 @@ impl AsyncSerialize for PublicNode {
         match self {
             Self::File(file) => file.serialize(serializer),
             Self::Dir(dir) => dir.async_serialize(serializer, store).await,
+            Self::Symlink(symlink) => symlink.serialize(serializer),
         }
     }
 }
@@ -210,12 +339,13 @@ mod public_node_tests {
     use std::rc::Rc;
 
     use chrono::Utc;
-    use libipld::Cid;
+    use libipld::{serde as ipld_serde, Cid, Ipld};
+    use semver::Version;
 
     use crate::{
         dagcbor,
-        public::{PublicDirectory, PublicFile, PublicNode},
-        MemoryBlockStore,
+        public::{PublicDirectory, PublicFile, PublicNode, PublicSymlink},
+        BlockStore, FsError, MemoryBlockStore, METADATA_VERSION,
     };
 
     #[async_std::test]
@@ -248,4 +378,63 @@ mod public_node_tests {
 
         assert_eq!(deserialized_node_dir, original_node_dir);
     }
+
+    #[async_std::test]
+    async fn serialized_public_symlink_can_be_deserialized() {
+        let store = &mut MemoryBlockStore::default();
+        let original_node_symlink =
+            PublicNode::Symlink(Rc::new(PublicSymlink::new(Utc::now(), "/pictures".into())));
+
+        let serialized_node_symlink = dagcbor::async_encode(&original_node_symlink, store)
+            .await
+            .unwrap();
+
+        let deserialized_node_symlink: PublicNode =
+            dagcbor::decode(serialized_node_symlink.as_ref()).unwrap();
+
+        assert_eq!(deserialized_node_symlink, original_node_symlink);
+    }
+
+    #[async_std::test]
+    async fn nodes_are_upgraded_or_rejected_by_metadata_version() {
+        let file = PublicFile::new(Utc::now(), Cid::default());
+        let with_version = |version: &str, mode: i128| {
+            let mut ipld = ipld_serde::to_ipld(&file).unwrap();
+            if let Ipld::Map(node) = &mut ipld {
+                if let Some(Ipld::Map(metadata)) = node.get_mut("metadata") {
+                    metadata.insert("version".into(), Ipld::String(version.into()));
+                    if let Some(Ipld::Map(unix_fs)) = metadata.get_mut("unix_fs") {
+                        unix_fs.insert("mode".into(), Ipld::Integer(mode));
+                    }
+                }
+            }
+            ipld
+        };
+
+        let node = PublicNode::try_from(with_version("1.0.0", 644)).unwrap();
+        let metadata = node.get_metadata();
+
+        assert!(node.as_file().is_ok());
+        assert_eq!(metadata.get_mode().bits(), 0o644);
+        assert_eq!(metadata.version, METADATA_VERSION);
+
+        let store = &mut MemoryBlockStore::default();
+        let cid = store
+            .put_serializable(&with_version("3.0.0", 0o644))
+            .await
+            .unwrap();
+
+        let error = store
+            .get_deserializable::<PublicNode>(&cid)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::UnsupportedVersion(
+                "metadata".into(),
+                Version::new(3, 0, 0)
+            ))
+        );
+    }
 }
@@ -0,0 +1,73 @@
+//! Encoding of the `previous` links of public nodes.
+
+use std::collections::BTreeSet;
+
+use libipld::{Cid, Ipld};
+use serde::{de, Deserialize, Deserializer};
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Decodes the `previous` links of a node.
+///
+/// Links are encoded as a list of CIDs. Nodes encoded before merges were supported have a single CID or
+/// null instead, which are read as a set of one or no links.
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<BTreeSet<Cid>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Ipld::deserialize(deserializer)? {
+        Ipld::Null => Ok(BTreeSet::new()),
+        Ipld::Link(cid) => Ok(BTreeSet::from([cid])),
+        Ipld::List(links) => links
+            .into_iter()
+            .map(|link| match link {
+                Ipld::Link(cid) => Ok(cid),
+                other => Err(de::Error::custom(format!(
+                    "Expected `Ipld::Link` got {:#?}",
+                    other
+                ))),
+            })
+            .collect(),
+        other => Err(de::Error::custom(format!(
+            "Expected `Ipld::List` got {:#?}",
+            other
+        ))),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod previous_tests {
+    use chrono::Utc;
+    use libipld::{serde as ipld_serde, Cid, Ipld};
+
+    use crate::public::PublicFile;
+
+    #[test]
+    fn legacy_single_previous_links_can_be_decoded() {
+        let file = PublicFile::new(Utc::now(), Cid::default());
+        let with_previous = |previous: Ipld| {
+            let mut ipld = ipld_serde::to_ipld(&file).unwrap();
+            if let Ipld::Map(node) = &mut ipld {
+                node.insert("previous".into(), previous);
+            }
+            ipld_serde::from_ipld::<PublicFile>(ipld)
+        };
+
+        let decoded = with_previous(Ipld::Link(Cid::default())).unwrap();
+        assert_eq!(
+            decoded.previous.into_iter().collect::<Vec<_>>(),
+            [Cid::default()]
+        );
+
+        let decoded = with_previous(Ipld::Null).unwrap();
+        assert!(decoded.previous.is_empty());
+
+        assert!(with_previous(Ipld::String("bafy".into())).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Rebasing local changes onto newer public trees.
+
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use crate::{BlockStore, FsError};
+
+use super::{JournalEntry, OpResult, PublicDirectory};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A local operation that couldn't be applied to the new base of a rebase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebaseConflict {
+    /// The journal entry of the operation.
+    pub entry: JournalEntry,
+    /// Why the operation failed, like a path that was removed or changed into a file in the new base.
+    pub error: FsError,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicDirectory {
+    /// Replays local operations on top of a newer root directory.
+    ///
+    /// `local_ops` are the journal entries recorded since the local tree diverged from the root it was based
+    /// on, oldest first, as returned by [`PublicDirectory::read_journal_since`]. Each operation is applied to
+    /// `new_base` with its recorded time and recorded in the journal of the result. Operations that fail
+    /// because of the paths they touch, like a missing parent or an entry of the wrong kind, are skipped and
+    /// returned as conflicts; other errors, like missing blocks, stop the rebase. The history of the rebased
+    /// root is based on `new_base`, as with [`PublicDirectory::base_history_on`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{Operation, PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let base = Rc::new(PublicDirectory::new(Utc::now()));
+    ///
+    ///     let OpResult { root_dir: local, .. } = Rc::clone(&base)
+    ///         .apply_and_record(
+    ///             Operation::Mkdir { path: vec!["pictures".into()] },
+    ///             Utc::now(),
+    ///             &mut store,
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir: remote, .. } = Rc::clone(&base)
+    ///         .mkdir(&["music".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let local_ops = local.read_journal_since(&base, &store).await.unwrap();
+    ///     let OpResult { root_dir, result } =
+    ///         PublicDirectory::rebase(&local_ops, remote, &mut store).await.unwrap();
+    ///
+    ///     assert!(result.is_empty());
+    ///     assert_eq!(root_dir.ls(&[], &store).await.unwrap().result.len(), 2);
+    /// }
+    /// ```
+    pub async fn rebase<B: BlockStore>(
+        local_ops: &[JournalEntry],
+        new_base: Rc<Self>,
+        store: &mut B,
+    ) -> Result<OpResult<Vec<RebaseConflict>>> {
+        let mut root_dir = Rc::clone(&new_base);
+        let mut conflicts = vec![];
+
+        for entry in local_ops {
+            let result = Rc::clone(&root_dir)
+                .apply_and_record(entry.operation.clone(), entry.time, store)
+                .await;
+
+            match result {
+                Ok(OpResult {
+                    root_dir: rebased, ..
+                }) => root_dir = rebased,
+                Err(error) => match error.downcast_ref::<FsError>() {
+                    Some(error) if utils::is_conflict(error) => conflicts.push(RebaseConflict {
+                        entry: entry.clone(),
+                        error: error.clone(),
+                    }),
+                    _ => return Err(error),
+                },
+            }
+        }
+
+        let OpResult { root_dir, .. } = root_dir.base_history_on(new_base, store).await?;
+
+        Ok(OpResult {
+            root_dir,
+            result: conflicts,
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use crate::FsError;
+
+    /// Whether an error is caused by the paths an operation touches in the tree it is applied to.
+    pub(super) fn is_conflict(error: &FsError) -> bool {
+        matches!(
+            error,
+            FsError::NotFound
+                | FsError::NotADirectory
+                | FsError::NotAFile
+                | FsError::FileAlreadyExists
+                | FsError::DirectoryAlreadyExists
+                | FsError::InvalidPath
+                | FsError::InvalidMoveLocation
+        )
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod rebase_tests {
+    use std::collections::BTreeSet;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use libipld::Cid;
+
+    use super::*;
+    use crate::{
+        public::{content, Operation},
+        MemoryBlockStore, WnfsPath,
+    };
+
+    #[async_std::test]
+    async fn rebase_replays_local_operations_and_reports_conflicts() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let report = content::put_bytes(b"report", &mut store).await.unwrap();
+        let notes = content::put_bytes(b"notes", &mut store).await.unwrap();
+
+        let mut base = Rc::new(PublicDirectory::new(time));
+        for path in ["docs/report.md", "docs/todo.txt"] {
+            let operation = Operation::Write {
+                path: path.parse::<WnfsPath>().unwrap().into_segments(),
+                content: report,
+            };
+
+            base = base
+                .apply_and_record(operation, time, &mut store)
+                .await
+                .unwrap()
+                .root_dir;
+        }
+
+        let operations = [
+            Operation::Write {
+                path: "docs/notes.md".parse::<WnfsPath>().unwrap().into_segments(),
+                content: notes,
+            },
+            Operation::Mkdir {
+                path: "pictures/cats".parse::<WnfsPath>().unwrap().into_segments(),
+            },
+            Operation::BasicMv {
+                from: "docs/todo.txt".parse::<WnfsPath>().unwrap().into_segments(),
+                to: "docs/done.txt".parse::<WnfsPath>().unwrap().into_segments(),
+            },
+            Operation::Rm {
+                path: "docs/report.md"
+                    .parse::<WnfsPath>()
+                    .unwrap()
+                    .into_segments(),
+            },
+        ];
+
+        let mut local = Rc::clone(&base);
+        for (index, operation) in operations.iter().enumerate() {
+            local = local
+                .apply_and_record(
+                    operation.clone(),
+                    time + Duration::seconds(index as i64 + 1),
+                    &mut store,
+                )
+                .await
+                .unwrap()
+                .root_dir;
+        }
+
+        let remote = Rc::clone(&base)
+            .rm(&"docs/todo.txt".parse::<WnfsPath>().unwrap(), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let remote = remote
+            .write(
+                &"pictures".parse::<WnfsPath>().unwrap(),
+                report,
+                time,
+                &store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let remote = remote
+            .base_history_on(Rc::clone(&base), &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let local_ops = local.read_journal_since(&base, &store).await.unwrap();
+        let OpResult { root_dir, result } =
+            PublicDirectory::rebase(&local_ops, Rc::clone(&remote), &mut store)
+                .await
+                .unwrap();
+
+        let conflicts = result
+            .iter()
+            .map(|conflict| (conflict.entry.operation.clone(), conflict.error.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            conflicts,
+            [
+                (operations[1].clone(), FsError::InvalidPath),
+                (operations[2].clone(), FsError::NotFound),
+            ]
+        );
+
+        let entries = Rc::clone(&root_dir)
+            .ls(&"docs".parse::<WnfsPath>().unwrap(), &store)
+            .await
+            .unwrap()
+            .result
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(entries, ["notes.md"]);
+        assert!(Rc::clone(&root_dir)
+            .get_node(&"pictures".parse::<WnfsPath>().unwrap(), &store)
+            .await
+            .unwrap()
+            .result
+            .unwrap()
+            .as_file()
+            .is_ok());
+
+        assert_eq!(
+            root_dir.get_previous(),
+            &BTreeSet::from([remote.store(&mut store).await.unwrap()])
+        );
+
+        let journal = root_dir.read_journal(&store).await.unwrap();
+        let recorded = journal
+            .into_iter()
+            .map(|entry| entry.operation)
+            .collect::<Vec<_>>();
+
+        assert_eq!(recorded, [operations[0].clone(), operations[3].clone()]);
+    }
+
+    #[async_std::test]
+    async fn rebase_stops_at_errors_that_arent_conflicts() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let base = Rc::new(PublicDirectory::new(time));
+
+        let local_ops = [JournalEntry {
+            operation: Operation::Mkdir {
+                path: vec!["docs".into(), "..".into()],
+            },
+            time,
+            root: Cid::default(),
+            previous: None,
+        }];
+
+        let error = PublicDirectory::rebase(&local_ops, base, &mut store)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::InvalidPathSegment("..".into()))
+        );
+    }
+}
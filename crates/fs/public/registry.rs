@@ -0,0 +1,388 @@
+//! Named snapshots and branches of public root directories.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use anyhow::{bail, ensure, Result};
+use libipld::{serde as ipld_serde, Cid, Ipld};
+use semver::Version;
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{migrate, BlockStore, FsError, REGISTRY_VERSION};
+
+use super::PublicDirectory;
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The kinds of name a [`RootRegistry`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RefKind {
+    /// A name for a root that never changes, like a release tag.
+    Snapshot,
+    /// A name for a line of work whose root can be moved forward to newer revisions.
+    Branch,
+}
+
+/// A registry of names for root directory CIDs, stored as an IPLD map in the block store.
+///
+/// Snapshots and branches share one namespace, so a name can only be used by one of them at a time.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::{PublicDirectory, RefKind, RootRegistry}, MemoryBlockStore};
+/// use chrono::Utc;
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let mut store = MemoryBlockStore::default();
+///     let root_cid = PublicDirectory::new(Utc::now()).store(&mut store).await.unwrap();
+///
+///     let mut registry = RootRegistry::new();
+///     registry.create_branch("main", root_cid).unwrap();
+///     registry.create_snapshot("release-2026-10", root_cid).unwrap();
+///
+///     let registry_cid = registry.store(&mut store).await.unwrap();
+///     let registry = RootRegistry::load(&registry_cid, &store).await.unwrap();
+///
+///     assert_eq!(registry.get("main"), Some((RefKind::Branch, root_cid)));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootRegistry {
+    version: Version,
+    snapshots: BTreeMap<String, Cid>,
+    branches: BTreeMap<String, Cid>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl RootRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            version: REGISTRY_VERSION,
+            snapshots: BTreeMap::new(),
+            branches: BTreeMap::new(),
+        }
+    }
+
+    /// Loads a registry from the block store.
+    pub async fn load<B: BlockStore>(cid: &Cid, store: &B) -> Result<Self> {
+        store.get_deserializable(cid).await
+    }
+
+    /// Stores the registry in the block store.
+    #[inline(always)]
+    pub async fn store<B: BlockStore>(&self, store: &mut B) -> Result<Cid> {
+        store.put_serializable(self).await
+    }
+
+    /// Names a root that never changes.
+    pub fn create_snapshot(&mut self, name: &str, root: Cid) -> Result<()> {
+        self.ensure_available(name)?;
+        self.snapshots.insert(name.to_string(), root);
+        Ok(())
+    }
+
+    /// Starts a branch at a root.
+    pub fn create_branch(&mut self, name: &str, root: Cid) -> Result<()> {
+        self.ensure_available(name)?;
+        self.branches.insert(name.to_string(), root);
+        Ok(())
+    }
+
+    /// Gets what a name is used for and the root it points at.
+    pub fn get(&self, name: &str) -> Option<(RefKind, Cid)> {
+        match (self.snapshots.get(name), self.branches.get(name)) {
+            (Some(root), _) => Some((RefKind::Snapshot, *root)),
+            (_, Some(root)) => Some((RefKind::Branch, *root)),
+            _ => None,
+        }
+    }
+
+    /// Lists the snapshots and branches sorted by name.
+    pub fn list(&self) -> Vec<(String, RefKind, Cid)> {
+        let snapshots = self
+            .snapshots
+            .iter()
+            .map(|(name, root)| (name.clone(), RefKind::Snapshot, *root));
+
+        let branches = self
+            .branches
+            .iter()
+            .map(|(name, root)| (name.clone(), RefKind::Branch, *root));
+
+        let mut refs = snapshots.chain(branches).collect::<Vec<_>>();
+        refs.sort();
+        refs
+    }
+
+    /// Removes a snapshot or branch. Returns what the name was used for and the root it pointed at.
+    pub fn delete(&mut self, name: &str) -> Result<(RefKind, Cid)> {
+        if let Some(root) = self.snapshots.remove(name) {
+            return Ok((RefKind::Snapshot, root));
+        }
+
+        match self.branches.remove(name) {
+            Some(root) => Ok((RefKind::Branch, root)),
+            None => bail!(FsError::RefNotFound(name.to_string())),
+        }
+    }
+
+    /// Checks if moving a branch to `root` is a fast-forward, which is the case if the root the branch points
+    /// at can be reached from `root` through `previous` links.
+    pub async fn is_fast_forward<B: BlockStore>(
+        &self,
+        name: &str,
+        root: &Cid,
+        store: &B,
+    ) -> Result<bool> {
+        match self.get(name) {
+            Some((RefKind::Branch, current)) => utils::is_ancestor(&current, root, store).await,
+            Some((RefKind::Snapshot, _)) => bail!(FsError::NotABranch(name.to_string())),
+            None => bail!(FsError::RefNotFound(name.to_string())),
+        }
+    }
+
+    /// Moves a branch to a newer root. Fails with [`FsError::NotAFastForward`] if the root the branch points
+    /// at isn't one of the revisions of `root`.
+    pub async fn fast_forward<B: BlockStore>(
+        &mut self,
+        name: &str,
+        root: Cid,
+        store: &B,
+    ) -> Result<()> {
+        ensure!(
+            self.is_fast_forward(name, &root, store).await?,
+            FsError::NotAFastForward(name.to_string())
+        );
+
+        self.branches.insert(name.to_string(), root);
+        Ok(())
+    }
+
+    /// Checks that a name isn't empty or used by a snapshot or branch yet.
+    fn ensure_available(&self, name: &str) -> Result<()> {
+        ensure!(!name.is_empty(), FsError::InvalidRefName(name.to_string()));
+        ensure!(
+            self.get(name).is_none(),
+            FsError::RefAlreadyExists(name.to_string())
+        );
+
+        Ok(())
+    }
+}
+
+impl Default for RootRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serialize for RootRegistry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let links = |refs: &BTreeMap<String, Cid>| {
+            Ipld::Map(
+                refs.iter()
+                    .map(|(name, root)| (name.clone(), Ipld::Link(*root)))
+                    .collect(),
+            )
+        };
+
+        Ipld::Map(BTreeMap::from([
+            ("version".into(), Ipld::String(self.version.to_string())),
+            ("snapshots".into(), links(&self.snapshots)),
+            ("branches".into(), links(&self.branches)),
+        ]))
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RootRegistry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ipld::deserialize(deserializer).and_then(|ipld| ipld.try_into().map_err(DeError::custom))
+    }
+}
+
+impl TryFrom<Ipld> for RootRegistry {
+    type Error = String;
+
+    /// Decodes a registry, upgrading it from the version it was encoded with to the current one.
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Map(mut map) => {
+                migrate("registry", &mut map, &REGISTRY_VERSION, &[])?;
+
+                let mut refs = |key: &str| {
+                    let refs = map.remove(key).ok_or(format!("Missing {key}"))?;
+                    ipld_serde::from_ipld::<BTreeMap<String, Cid>>(refs).map_err(|e| e.to_string())
+                };
+
+                Ok(Self {
+                    version: REGISTRY_VERSION,
+                    snapshots: refs("snapshots")?,
+                    branches: refs("branches")?,
+                })
+            }
+            other => Err(format!("Expected `Ipld::Map`, got {:#?}", other)),
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Utilities
+//--------------------------------------------------------------------------------------------------
+
+mod utils {
+    use super::*;
+
+    /// Checks if `ancestor` is `root` or one of the revisions reachable from it through `previous` links.
+    pub(super) async fn is_ancestor<B: BlockStore>(
+        ancestor: &Cid,
+        root: &Cid,
+        store: &B,
+    ) -> Result<bool> {
+        let mut visited = BTreeSet::from([*root]);
+        let mut pending = VecDeque::from([*root]);
+
+        while let Some(cid) = pending.pop_front() {
+            if cid == *ancestor {
+                return Ok(true);
+            }
+
+            let dir = store.get_deserializable::<PublicDirectory>(&cid).await?;
+            for previous in dir.previous {
+                if visited.insert(previous) {
+                    pending.push_back(previous);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod registry_tests {
+    use std::rc::Rc;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::MemoryBlockStore;
+
+    #[async_std::test]
+    async fn registry_names_can_be_created_listed_and_deleted() {
+        let mut store = MemoryBlockStore::default();
+        let root = PublicDirectory::new(Utc::now())
+            .store(&mut store)
+            .await
+            .unwrap();
+
+        let mut registry = RootRegistry::new();
+        registry.create_branch("main", root).unwrap();
+        registry.create_branch("draft", root).unwrap();
+        registry.create_snapshot("release-2026-10", root).unwrap();
+
+        let error = registry.create_snapshot("main", root).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::RefAlreadyExists("main".into()))
+        );
+        assert!(registry.create_branch("", root).is_err());
+
+        let registry_cid = registry.store(&mut store).await.unwrap();
+        let mut registry = RootRegistry::load(&registry_cid, &store).await.unwrap();
+
+        assert_eq!(
+            registry.list(),
+            [
+                ("draft".into(), RefKind::Branch, root),
+                ("main".into(), RefKind::Branch, root),
+                ("release-2026-10".into(), RefKind::Snapshot, root),
+            ]
+        );
+
+        assert_eq!(registry.delete("draft").unwrap(), (RefKind::Branch, root));
+        assert_eq!(registry.get("draft"), None);
+        assert!(registry.delete("draft").is_err());
+    }
+
+    #[async_std::test]
+    async fn only_branches_can_be_fast_forwarded_to_descendants() {
+        let mut store = MemoryBlockStore::default();
+        let base = Rc::new(PublicDirectory::new(Utc::now()));
+        let base_cid = base.store(&mut store).await.unwrap();
+
+        let next = Rc::clone(&base)
+            .mkdir(&["pictures".into()], Utc::now(), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let next = next
+            .base_history_on(Rc::clone(&base), &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let next_cid = next.store(&mut store).await.unwrap();
+
+        let unrelated = Rc::clone(&base)
+            .mkdir(&["music".into()], Utc::now(), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let unrelated_cid = unrelated.store(&mut store).await.unwrap();
+
+        let mut registry = RootRegistry::new();
+        registry.create_branch("main", base_cid).unwrap();
+        registry.create_snapshot("v1", base_cid).unwrap();
+
+        assert!(registry
+            .is_fast_forward("main", &next_cid, &store)
+            .await
+            .unwrap());
+
+        let error = registry
+            .fast_forward("main", unrelated_cid, &store)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::NotAFastForward("main".into()))
+        );
+
+        registry
+            .fast_forward("main", next_cid, &store)
+            .await
+            .unwrap();
+        assert_eq!(registry.get("main"), Some((RefKind::Branch, next_cid)));
+
+        let error = registry
+            .fast_forward("v1", next_cid, &store)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::NotABranch("v1".into()))
+        );
+    }
+}
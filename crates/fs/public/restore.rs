@@ -0,0 +1,291 @@
+//! Restoring public paths from past revisions.
+
+use std::{collections::BTreeSet, rc::Rc};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libipld::Cid;
+
+use crate::{error, validate_segments, BlockStore, FsError};
+
+use super::{OpResult, PathNodesResult, PublicDirectory, PublicLink};
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicDirectory {
+    /// Puts the node at a path back the way it was in a past revision, leaving the rest of the tree as it is.
+    ///
+    /// `revision_cid` is the CID of a root directory, such as one yielded by [`PublicDirectory::get_history`].
+    /// The node is copied from that revision with the given modified time, and missing directories along the
+    /// path are created. The restored node's `previous` link points at the node it replaces, or at the node
+    /// it was copied from if the path is missing in this tree. Directories are restored with everything below
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let path = ["report.md".into()];
+    ///
+    ///     let OpResult { root_dir: old_root, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write_bytes(&path, b"draft", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let revision_cid = old_root.store(&mut store).await.unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = old_root
+    ///         .write_bytes(&path, b"oops", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .restore(&path, &revision_cid, Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir.read_bytes(&path, &mut store).await.unwrap();
+    ///
+    ///     assert_eq!(result, b"draft");
+    /// }
+    /// ```
+    pub async fn restore<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        revision_cid: &Cid,
+        time: DateTime<Utc>,
+        store: &mut B,
+    ) -> Result<OpResult<()>> {
+        validate_segments(path_segments)?;
+        let (name, directory_path) = match path_segments.split_last() {
+            Some(split) => split,
+            None => return error(FsError::InvalidPath),
+        };
+
+        let revision = Rc::new(
+            store
+                .get_deserializable::<PublicDirectory>(revision_cid)
+                .await?,
+        );
+        let revision_link = match revision.get_path_nodes(directory_path, store).await? {
+            PathNodesResult::Complete(path_nodes) => path_nodes.tail.userland.get(name).cloned(),
+            _ => None,
+        };
+
+        let revision_link = match revision_link {
+            Some(link) => link,
+            None => return error(FsError::NotFound),
+        };
+
+        let mut path_nodes = self
+            .get_path_nodes_or_create(directory_path, time, store)
+            .await?;
+
+        let previous = match path_nodes.tail.userland.get(name) {
+            Some(link) => *link.resolve_cid(store).await?,
+            None => *revision_link.resolve_cid(store).await?,
+        };
+
+        let restored = revision_link
+            .resolve_value(store)
+            .await?
+            .update_mtime(time)
+            .update_previous(BTreeSet::from([previous]));
+
+        let mut directory = (*path_nodes.tail).clone();
+        directory
+            .replace_entry(name, Some(PublicLink::new(restored)), store)
+            .await?;
+
+        path_nodes.tail = Rc::new(directory);
+
+        Ok(OpResult {
+            root_dir: path_nodes
+                .reconstruct_at(time)
+                .complete_usage(store)
+                .await?,
+            result: (),
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod restore_tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::{public::PublicNode, MemoryBlockStore, WnfsPath};
+
+    async fn read(
+        root_dir: &Rc<PublicDirectory>,
+        path: &str,
+        store: &mut MemoryBlockStore,
+    ) -> Vec<u8> {
+        Rc::clone(root_dir)
+            .read_bytes(&path.parse::<WnfsPath>().unwrap(), store)
+            .await
+            .unwrap()
+            .result
+    }
+
+    async fn node(
+        root_dir: &Rc<PublicDirectory>,
+        path: &str,
+        store: &MemoryBlockStore,
+    ) -> PublicNode {
+        Rc::clone(root_dir)
+            .get_node(&path.parse::<WnfsPath>().unwrap(), store)
+            .await
+            .unwrap()
+            .result
+            .unwrap()
+    }
+
+    #[async_std::test]
+    async fn restore_only_changes_the_restored_path() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+
+        let mut revision_cids = vec![];
+        let mut root_dir = Rc::new(PublicDirectory::new(time));
+        for (version, content) in [b"v1", b"v2", b"v3"].iter().enumerate() {
+            let time = time + Duration::days(version as i64);
+            for path in ["docs/report.md", "docs/notes.md"] {
+                root_dir = root_dir
+                    .write_bytes(
+                        &path.parse::<WnfsPath>().unwrap(),
+                        *content,
+                        time,
+                        &mut store,
+                    )
+                    .await
+                    .unwrap()
+                    .root_dir;
+            }
+
+            root_dir = root_dir
+                .write_bytes(
+                    &"todo.txt".parse::<WnfsPath>().unwrap(),
+                    *content,
+                    time,
+                    &mut store,
+                )
+                .await
+                .unwrap()
+                .root_dir;
+
+            revision_cids.push(root_dir.store(&mut store).await.unwrap());
+        }
+
+        let replaced = node(&root_dir, "docs/report.md", &store).await;
+        let restore_time = time + Duration::days(5);
+
+        let root_dir = Rc::clone(&root_dir)
+            .restore(
+                &"docs/report.md".parse::<WnfsPath>().unwrap(),
+                &revision_cids[0],
+                restore_time,
+                &mut store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        assert_eq!(read(&root_dir, "docs/report.md", &mut store).await, b"v1");
+        assert_eq!(read(&root_dir, "docs/notes.md", &mut store).await, b"v3");
+        assert_eq!(read(&root_dir, "todo.txt", &mut store).await, b"v3");
+
+        let restored = node(&root_dir, "docs/report.md", &store).await;
+
+        assert_eq!(restored.get_metadata().get_modified(), restore_time);
+        assert_eq!(
+            restored.get_previous(),
+            &BTreeSet::from([replaced.store(&mut store).await.unwrap()])
+        );
+
+        let root_dir = root_dir
+            .restore(
+                &"docs".parse::<WnfsPath>().unwrap(),
+                &revision_cids[1],
+                restore_time,
+                &mut store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        assert_eq!(read(&root_dir, "docs/report.md", &mut store).await, b"v2");
+        assert_eq!(read(&root_dir, "docs/notes.md", &mut store).await, b"v2");
+        assert_eq!(read(&root_dir, "todo.txt", &mut store).await, b"v3");
+        assert_eq!(root_dir.metadata.get_size(), Some(6));
+    }
+
+    #[async_std::test]
+    async fn restore_recreates_removed_paths_and_rejects_missing_ones() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+
+        let old_root = Rc::new(PublicDirectory::new(time))
+            .write_bytes(
+                &"docs/report.md".parse::<WnfsPath>().unwrap(),
+                b"v1",
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let revision_cid = old_root.store(&mut store).await.unwrap();
+        let original = node(&old_root, "docs/report.md", &store).await;
+
+        let root_dir = old_root
+            .rm(&"docs".parse::<WnfsPath>().unwrap(), &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let root_dir = root_dir
+            .restore(
+                &"docs/report.md".parse::<WnfsPath>().unwrap(),
+                &revision_cid,
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap()
+            .root_dir;
+
+        let restored = node(&root_dir, "docs/report.md", &store).await;
+
+        assert_eq!(read(&root_dir, "docs/report.md", &mut store).await, b"v1");
+        assert_eq!(
+            restored.get_previous(),
+            &BTreeSet::from([original.store(&mut store).await.unwrap()])
+        );
+
+        let error = root_dir
+            .restore(
+                &"missing.md".parse::<WnfsPath>().unwrap(),
+                &revision_cid,
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.downcast_ref::<FsError>(), Some(&FsError::NotFound));
+    }
+}
@@ -0,0 +1,410 @@
+//! Read-only views of past revisions of public trees.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    rc::Rc,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libipld::Cid;
+
+use crate::{error, BlockStore, FsError, Metadata};
+
+use super::{OpResult, PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A read-only view of a revision of a WNFS public file system.
+///
+/// Revisions are found by following the `previous` links of a root directory with
+/// [`PublicDirectory::at_time`] or [`PublicDirectory::at_revision`].
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore};
+/// use std::rc::Rc;
+/// use chrono::{Duration, Utc};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let mut store = MemoryBlockStore::default();
+///     let yesterday = Utc::now() - Duration::days(1);
+///     let base = Rc::new(PublicDirectory::new(yesterday));
+///
+///     let OpResult { root_dir, .. } = Rc::clone(&base)
+///         .mkdir(&["pictures".into()], Utc::now(), &store)
+///         .await
+///         .unwrap();
+///
+///     let OpResult { root_dir, .. } = root_dir
+///         .base_history_on(base, &mut store)
+///         .await
+///         .unwrap();
+///
+///     let revision = root_dir
+///         .at_time(yesterday + Duration::hours(1), &store)
+///         .await
+///         .unwrap();
+///
+///     assert_eq!(revision.get_index(), 1);
+///     assert!(revision.ls(&[], &store).await.unwrap().is_empty());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PublicRevision {
+    root_dir: Rc<PublicDirectory>,
+    index: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicRevision {
+    /// Gets the fewest `previous` links that have to be followed to get to this revision.
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the time the root directory of this revision was last modified.
+    pub fn get_modified(&self) -> DateTime<Utc> {
+        self.root_dir.metadata.get_modified()
+    }
+
+    /// Reads specified file content from the revision.
+    pub async fn read<B: BlockStore>(
+        &self,
+        path_segments: &[String],
+        store: &mut B,
+    ) -> Result<Cid> {
+        let OpResult { result, .. } = Rc::clone(&self.root_dir).read(path_segments, store).await?;
+
+        Ok(result)
+    }
+
+    /// Returns the name and metadata of the direct children of a directory in the revision.
+    pub async fn ls<B: BlockStore>(
+        &self,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<Vec<(String, Metadata)>> {
+        let OpResult { result, .. } = Rc::clone(&self.root_dir).ls(path_segments, store).await?;
+
+        Ok(result)
+    }
+
+    /// Gets the node at the specified path in the revision.
+    pub async fn get_node<B: BlockStore>(
+        &self,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<Option<PublicNode>> {
+        let OpResult { result, .. } = Rc::clone(&self.root_dir)
+            .get_node(path_segments, store)
+            .await?;
+
+        Ok(result)
+    }
+}
+
+impl PublicDirectory {
+    /// Gets the newest revision of this root directory that was last modified at or before `time`.
+    ///
+    /// Revisions are dated by the modified time of their root. Operations that take a time, like
+    /// [`PublicDirectory::write`] and [`PublicDirectory::rm_at`], give it to the root, so their revisions are
+    /// found at the time of the change. Changes made without a time, like [`PublicDirectory::rm`],
+    /// [`PublicDirectory::chmod`] and the extended attribute setters, leave the modified time of the root as it
+    /// was, so their revisions are found at the time of the change before them. [`PublicDirectory::set_times`]
+    /// on the root sets it directly, and a merged root has the later modified time of the two roots.
+    ///
+    /// This directory is the newest revision and its `previous` links lead to older ones. When a merge gives a
+    /// revision several previous ones, the newest revision found along any of them is used. Fails with
+    /// [`FsError::RevisionNotFound`] if every revision was modified after `time`.
+    pub async fn at_time<B: BlockStore>(
+        self: Rc<Self>,
+        time: DateTime<Utc>,
+        store: &B,
+    ) -> Result<PublicRevision> {
+        let mut visited = BTreeSet::new();
+        let mut pending = VecDeque::from([(self, 0)]);
+        let mut found: Option<PublicRevision> = None;
+
+        while let Some((root_dir, index)) = pending.pop_front() {
+            let modified = root_dir.metadata.get_modified();
+            if modified <= time {
+                if found
+                    .as_ref()
+                    .map_or(true, |found| modified > found.get_modified())
+                {
+                    found = Some(PublicRevision { root_dir, index });
+                }
+
+                continue;
+            }
+
+            for cid in root_dir.get_previous() {
+                if visited.insert(*cid) {
+                    let previous = Rc::new(store.get_deserializable(cid).await?);
+                    pending.push_back((previous, index + 1));
+                }
+            }
+        }
+
+        match found {
+            Some(revision) => Ok(revision),
+            None => error(FsError::RevisionNotFound),
+        }
+    }
+
+    /// Gets the revision of this root directory found by following `index` `previous` links.
+    ///
+    /// An index of zero is this directory. If merges make several revisions the same number of links away, the
+    /// one modified last is used. Fails with [`FsError::RevisionNotFound`] if there are fewer revisions.
+    pub async fn at_revision<B: BlockStore>(
+        self: Rc<Self>,
+        index: usize,
+        store: &B,
+    ) -> Result<PublicRevision> {
+        let mut visited = BTreeSet::new();
+        let mut revisions = vec![self];
+
+        for _ in 0..index {
+            let mut previous_revisions = vec![];
+            for root_dir in revisions {
+                for cid in root_dir.get_previous() {
+                    if visited.insert(*cid) {
+                        previous_revisions.push(Rc::new(store.get_deserializable(cid).await?));
+                    }
+                }
+            }
+
+            revisions = previous_revisions;
+        }
+
+        match revisions
+            .into_iter()
+            .max_by_key(|root_dir| root_dir.metadata.get_modified())
+        {
+            Some(root_dir) => Ok(PublicRevision { root_dir, index }),
+            None => error(FsError::RevisionNotFound),
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod revision_tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+    use crate::{public::MergePolicy, MemoryBlockStore, UnixFsMode};
+
+    /// Creates three revisions, one day apart, each with one more file than the one before.
+    async fn revisions(time: DateTime<Utc>, store: &mut MemoryBlockStore) -> Rc<PublicDirectory> {
+        let mut root_dir = Rc::new(PublicDirectory::new(time));
+
+        for (day, name) in [(1, "monday.txt"), (2, "tuesday.txt")] {
+            let time = time + Duration::days(day);
+            let base = Rc::clone(&root_dir);
+
+            let new_root = Rc::clone(&root_dir)
+                .write_bytes(&["notes".into(), name.into()], name.as_bytes(), time, store)
+                .await
+                .unwrap()
+                .root_dir;
+
+            let new_root = new_root
+                .base_history_on(base, store)
+                .await
+                .unwrap()
+                .root_dir;
+
+            root_dir = new_root;
+        }
+
+        root_dir
+    }
+
+    #[async_std::test]
+    async fn at_time_finds_the_newest_revision_before_the_time() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let root_dir = revisions(time, &mut store).await;
+
+        let revision = Rc::clone(&root_dir)
+            .at_time(time + Duration::days(1) + Duration::hours(12), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 1);
+        assert_eq!(revision.get_modified(), time + Duration::days(1));
+
+        let entries = revision.ls(&["notes".into()], &store).await.unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "monday.txt");
+        assert!(revision
+            .get_node(&["notes".into(), "tuesday.txt".into()], &store)
+            .await
+            .unwrap()
+            .is_none());
+
+        let cid = revision
+            .read(&["notes".into(), "monday.txt".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            crate::public::content::get_bytes(&cid, &store)
+                .await
+                .unwrap(),
+            b"monday.txt"
+        );
+
+        let revision = Rc::clone(&root_dir)
+            .at_time(time + Duration::days(5), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 0);
+
+        let error = root_dir
+            .at_time(time - Duration::days(1), &store)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<FsError>(),
+            Some(&FsError::RevisionNotFound)
+        );
+    }
+
+    #[async_std::test]
+    async fn at_time_dates_revisions_by_the_modified_time_of_their_root() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let base = Rc::new(PublicDirectory::new(time));
+
+        let written = Rc::clone(&base)
+            .write_bytes(
+                &["a.txt".into()],
+                b"a",
+                time + Duration::days(1),
+                &mut store,
+            )
+            .await
+            .unwrap()
+            .root_dir
+            .base_history_on(base, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        // A change without a time is found at the time of the change before it.
+        let chmodded = Rc::clone(&written)
+            .chmod(&["a.txt".into()], UnixFsMode::new(0o600).unwrap(), &store)
+            .await
+            .unwrap()
+            .root_dir
+            .base_history_on(written, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let revision = Rc::clone(&chmodded)
+            .at_time(time + Duration::days(1), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 0);
+
+        let retimed = Rc::clone(&chmodded)
+            .set_times(&[], None, Some(time + Duration::days(3)), &store)
+            .await
+            .unwrap()
+            .root_dir
+            .base_history_on(chmodded, &mut store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let revision = Rc::clone(&retimed)
+            .at_time(time + Duration::days(2), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 1);
+
+        // A merged root is dated by the later of the merged roots, so the earlier one is still found.
+        let mut sides = vec![];
+        for (day, name) in [(4, "ours.txt"), (5, "theirs.txt")] {
+            let side = Rc::clone(&retimed)
+                .write_bytes(
+                    &[name.into()],
+                    name.as_bytes(),
+                    time + Duration::days(day),
+                    &mut store,
+                )
+                .await
+                .unwrap()
+                .root_dir
+                .base_history_on(Rc::clone(&retimed), &mut store)
+                .await
+                .unwrap()
+                .root_dir;
+
+            sides.push(side);
+        }
+
+        let [ours, theirs]: [_; 2] = sides.try_into().unwrap();
+        let merged = PublicDirectory::merge(
+            ours,
+            theirs,
+            retimed,
+            &MergePolicy::LastWriterWins,
+            &mut store,
+        )
+        .await
+        .unwrap()
+        .root_dir;
+
+        let revision = Rc::clone(&merged)
+            .at_time(time + Duration::days(4) + Duration::hours(12), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 1);
+        assert!(revision
+            .get_node(&["theirs.txt".into()], &store)
+            .await
+            .unwrap()
+            .is_none());
+
+        let revision = merged
+            .at_time(time + Duration::days(5), &store)
+            .await
+            .unwrap();
+
+        assert_eq!(revision.get_index(), 0);
+    }
+
+    #[async_std::test]
+    async fn at_revision_follows_previous_links() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let root_dir = revisions(time, &mut store).await;
+
+        let revision = Rc::clone(&root_dir).at_revision(2, &store).await.unwrap();
+
+        assert_eq!(revision.get_modified(), time);
+        assert!(revision.ls(&[], &store).await.unwrap().is_empty());
+        assert!(root_dir.at_revision(3, &store).await.is_err());
+    }
+}
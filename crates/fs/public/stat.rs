@@ -0,0 +1,237 @@
+//! Node information for public paths.
+
+use std::{collections::BTreeSet, rc::Rc};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libipld::Cid;
+
+use crate::{error, BlockStore, FsError, UnixFsMode, UnixFsNodeKind};
+
+use super::{content, OpResult, PathNodesResult, PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Information about a node in a WNFS public file system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    /// The kind of node.
+    pub kind: UnixFsNodeKind,
+    /// The permissions of the node.
+    pub mode: UnixFsMode,
+    /// When the node was created.
+    pub created: DateTime<Utc>,
+    /// When the node was last modified.
+    pub modified: DateTime<Utc>,
+    /// The CID of a file's content. Directories and symlinks don't have one.
+    pub content_cid: Option<Cid>,
+    /// The CID of the node itself.
+    pub node_cid: Cid,
+    /// The CIDs of the previous revisions of the node.
+    pub previous: BTreeSet<Cid>,
+    /// The size of a file's content or the total content size of the files below a directory, if it is known.
+    pub size: Option<u64>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl Stat {
+    /// Creates the stat of a node that has been stored under the given CID.
+    ///
+    /// The size of a file without a recorded size is read from its content, and left unknown if that fails.
+    async fn new<B: BlockStore>(node: &PublicNode, node_cid: Cid, store: &B) -> Self {
+        let content_cid = match node {
+            PublicNode::File(file) => Some(file.userland),
+            _ => None,
+        };
+
+        let metadata = node.get_metadata();
+
+        let size = match (metadata.get_size(), &content_cid) {
+            (Some(size), _) => Some(size),
+            (None, Some(cid)) => content::get_size(cid, store).await.ok(),
+            (None, None) => None,
+        };
+
+        Self {
+            kind: metadata.unix_fs.kind,
+            mode: metadata.get_mode(),
+            created: metadata.get_created(),
+            modified: metadata.get_modified(),
+            content_cid,
+            node_cid,
+            previous: node.get_previous().clone(),
+            size,
+        }
+    }
+}
+
+impl PublicDirectory {
+    /// Gets information about the node at the given path.
+    ///
+    /// The node is stored if it hasn't been already, so that its CID can be included. The content size
+    /// of a file is only known if its content was written as chunked bytes or is available in the store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, MemoryBlockStore, UnixFsNodeKind};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write_bytes(&["hello.txt".into()], b"hello world", Utc::now(), &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result: stat, .. } = root_dir
+    ///         .stat(&["hello.txt".into()], &mut store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(stat.kind, UnixFsNodeKind::File);
+    ///     assert_eq!(stat.size, Some(11));
+    /// }
+    /// ```
+    pub async fn stat<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &mut B,
+    ) -> Result<OpResult<Stat>> {
+        let root_dir = Rc::clone(&self);
+
+        let (path, name) = match path_segments.split_last() {
+            Some((name, path)) => (path, name),
+            None => {
+                let node_cid = self.store(store).await?;
+                let result = Stat::new(&PublicNode::Dir(self), node_cid, store).await;
+                return Ok(OpResult { root_dir, result });
+            }
+        };
+
+        let dir = match self.get_path_nodes(path, store).await? {
+            PathNodesResult::Complete(path_nodes) => path_nodes.tail,
+            _ => return error(FsError::NotFound),
+        };
+
+        let link = match dir.userland.get(name) {
+            Some(link) => link,
+            None => return error(FsError::NotFound),
+        };
+
+        let node_cid = *link.resolve_cid(store).await?;
+        let node = link.resolve_value(store).await?;
+        let result = Stat::new(node, node_cid, store).await;
+
+        Ok(OpResult { root_dir, result })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod stat_tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{public::PublicFile, MemoryBlockStore};
+
+    #[async_std::test]
+    async fn stat_returns_node_information() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+
+        let OpResult { root_dir, result } = Rc::new(PublicDirectory::new(time))
+            .write_bytes(
+                &["docs".into(), "hello.txt".into()],
+                b"hello world",
+                time,
+                &mut store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult {
+            root_dir,
+            result: stat,
+        } = root_dir
+            .stat(&["docs".into(), "hello.txt".into()], &mut store)
+            .await
+            .unwrap();
+
+        let file = store
+            .get_deserializable::<PublicFile>(&stat.node_cid)
+            .await
+            .unwrap();
+
+        assert_eq!(stat.kind, UnixFsNodeKind::File);
+        assert_eq!(stat.created, time);
+        assert_eq!(stat.modified, time);
+        assert_eq!(stat.content_cid, Some(result));
+        assert!(stat.previous.is_empty());
+        assert_eq!(stat.size, Some(11));
+        assert_eq!(file.userland, result);
+
+        let OpResult { result: stat, .. } =
+            root_dir.stat(&["docs".into()], &mut store).await.unwrap();
+
+        assert_eq!(stat.kind, UnixFsNodeKind::Dir);
+        assert_eq!(stat.content_cid, None);
+        assert_eq!(stat.size, Some(11));
+    }
+
+    #[async_std::test]
+    async fn stat_leaves_sizes_that_cant_be_read_unknown() {
+        let time = Utc.timestamp(1_650_000_000, 0);
+        let mut store = MemoryBlockStore::default();
+        let missing = content::put_bytes(b"elsewhere", &mut MemoryBlockStore::default())
+            .await
+            .unwrap();
+
+        let root_dir = Rc::new(PublicDirectory::new(time))
+            .write(&["unknown.bin".into()], Cid::default(), time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let root_dir = root_dir
+            .write(&["missing.txt".into()], missing, time, &store)
+            .await
+            .unwrap()
+            .root_dir;
+
+        let OpResult { root_dir, result } = root_dir
+            .stat(&["unknown.bin".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result.size, None);
+
+        let OpResult { result, .. } = root_dir
+            .stat(&["missing.txt".into()], &mut store)
+            .await
+            .unwrap();
+
+        assert_eq!(result.size, None);
+    }
+
+    #[async_std::test]
+    async fn stat_fails_on_missing_path() {
+        let mut store = MemoryBlockStore::default();
+        let root_dir = Rc::new(PublicDirectory::new(Utc::now()));
+
+        let result = root_dir.stat(&["missing".into()], &mut store).await;
+
+        assert!(result.is_err());
+    }
+}
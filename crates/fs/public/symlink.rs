@@ -0,0 +1,118 @@
+//! Public fs symlink node.
+
+use std::{collections::BTreeSet, rc::Rc};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libipld::Cid;
+use serde::{Deserialize, Serialize};
+
+use crate::{BlockStore, Id, Metadata, UnixFsNodeKind};
+
+/// A symbolic link in a WNFS public file system.
+///
+/// The target is a slash-separated path. It is resolved from the root of the file system if it starts with a
+/// `/` and from the directory containing the link otherwise. `.` and `..` segments are allowed in targets.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::{public::PublicSymlink, Id};
+/// use chrono::Utc;
+///
+/// let symlink = PublicSymlink::new(Utc::now(), "../pictures/cats".into());
+///
+/// println!("id = {}", symlink.get_id());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicSymlink {
+    pub(crate) metadata: Metadata,
+    pub(crate) target: String,
+    #[serde(deserialize_with = "super::previous::deserialize")]
+    pub(crate) previous: BTreeSet<Cid>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicSymlink {
+    /// Creates a new symlink pointing to the given target path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::PublicSymlink, Id};
+    /// use chrono::Utc;
+    ///
+    /// let symlink = PublicSymlink::new(Utc::now(), "/pictures/cats".into());
+    ///
+    /// assert_eq!(symlink.get_target(), "/pictures/cats");
+    /// ```
+    pub fn new(time: DateTime<Utc>, target: String) -> Self {
+        Self {
+            metadata: Metadata::new(time, UnixFsNodeKind::SymLink),
+            target,
+            previous: BTreeSet::new(),
+        }
+    }
+
+    /// Gets the path the symlink points to.
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+
+    /// Gets the CIDs of the revisions this symlink was derived from.
+    pub fn get_previous(self: &Rc<Self>) -> &BTreeSet<Cid> {
+        &self.previous
+    }
+
+    /// Stores symlink in provided block store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::PublicSymlink, Id, MemoryBlockStore};
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let mut store = MemoryBlockStore::default();
+    ///     let symlink = PublicSymlink::new(Utc::now(), "pictures".into());
+    ///
+    ///     symlink.store(&mut store).await.unwrap();
+    /// }
+    /// ```
+    #[inline(always)]
+    pub async fn store<B: BlockStore>(&self, store: &mut B) -> Result<Cid> {
+        store.put_serializable(self).await
+    }
+}
+
+impl Id for PublicSymlink {
+    fn get_id(&self) -> String {
+        format!("{:p}", &self.metadata)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod public_symlink_tests {
+    use chrono::Utc;
+
+    use crate::{dagcbor, public::PublicSymlink};
+
+    #[async_std::test]
+    async fn serialized_public_symlink_can_be_deserialized() {
+        let original_symlink = PublicSymlink::new(Utc::now(), "../pictures".into());
+
+        let serialized_symlink = dagcbor::encode(&original_symlink).unwrap();
+        let deserialized_symlink: PublicSymlink =
+            dagcbor::decode(serialized_symlink.as_ref()).unwrap();
+
+        assert_eq!(deserialized_symlink, original_symlink);
+    }
+}
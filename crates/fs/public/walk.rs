@@ -0,0 +1,310 @@
+//! Recursive traversal of a public file tree.
+
+use std::{collections::VecDeque, rc::Rc};
+
+use anyhow::Result;
+use async_stream::try_stream;
+use futures::Stream;
+
+use crate::{error, BlockStore, FsError};
+
+use super::{PathNodesResult, PublicDirectory, PublicNode};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The order in which nodes are visited by [`PublicDirectory::walk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Visits a directory, then everything under it, before moving on to its next sibling.
+    DepthFirst,
+    /// Visits all nodes at one depth before any node at the next depth.
+    BreadthFirst,
+}
+
+/// A predicate that decides whether the children of a directory are skipped.
+///
+/// It is given the path of the directory and the directory itself.
+pub type WalkPrune = Box<dyn Fn(&[String], &PublicDirectory) -> bool>;
+
+/// Options for [`PublicDirectory::walk`].
+///
+/// # Examples
+///
+/// ```
+/// use wnfs::public::{WalkOptions, WalkOrder};
+///
+/// let options = WalkOptions {
+///     order: WalkOrder::BreadthFirst,
+///     max_depth: Some(2),
+///     prune: Some(Box::new(|path, _| path.last().map(|name| name.starts_with('.')).unwrap_or(false))),
+/// };
+/// ```
+pub struct WalkOptions {
+    /// The order in which nodes are visited.
+    pub order: WalkOrder,
+    /// The maximum depth to visit. The direct children of the walked directory are at depth 1.
+    pub max_depth: Option<usize>,
+    /// Directories for which this returns `true` are still visited but their children are not resolved.
+    pub prune: Option<WalkPrune>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self::from(WalkOrder::DepthFirst)
+    }
+}
+
+impl From<WalkOrder> for WalkOptions {
+    fn from(order: WalkOrder) -> Self {
+        Self {
+            order,
+            max_depth: None,
+            prune: None,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Checks if the children of a directory found during a walk should be visited.
+    fn should_descend(&self, path: &[String], depth: usize, dir: &PublicDirectory) -> bool {
+        if matches!(self.max_depth, Some(max_depth) if depth >= max_depth) {
+            return false;
+        }
+
+        match &self.prune {
+            Some(prune) => !prune(path, dir),
+            None => true,
+        }
+    }
+}
+
+impl PublicDirectory {
+    /// Visits every node under the directory at the given path.
+    ///
+    /// Each item is the full path of a node from this directory along with the node itself. The node
+    /// at the given path is not part of the output. A directory's children are only resolved from the
+    /// store when the walk reaches them, so pruned subtrees are never loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult, WalkOrder}, MemoryBlockStore};
+    /// use libipld::cid::Cid;
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    /// use futures_util::pin_mut;
+    /// use async_std::stream::StreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .write(
+    ///             &["pictures".into(), "cats".into(), "tabby.png".into()],
+    ///             Cid::default(),
+    ///             Utc::now(),
+    ///             &store
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let nodes = root_dir.walk(&[], WalkOrder::DepthFirst, &store);
+    ///
+    ///     pin_mut!(nodes);
+    ///
+    ///     while let Some(item) = nodes.next().await {
+    ///         let (path, node) = item.unwrap();
+    ///         println!("{} is a {:?}", path.join("/"), node.kind());
+    ///     }
+    /// }
+    /// ```
+    pub fn walk<'a, B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &'a [String],
+        options: impl Into<WalkOptions>,
+        store: &'a B,
+    ) -> impl Stream<Item = Result<(Vec<String>, PublicNode)>> + 'a {
+        let options = options.into();
+
+        try_stream! {
+            let dir = match self.get_path_nodes(path_segments, store).await? {
+                PathNodesResult::Complete(path_nodes) => path_nodes.tail,
+                _ => error(FsError::NotFound)?,
+            };
+
+            let mut pending = VecDeque::new();
+            if options.max_depth != Some(0) {
+                push_children(&mut pending, &options, path_segments, 0, &dir, store).await?;
+            }
+
+            while let Some((path, depth, node)) = pending.pop_front() {
+                if let PublicNode::Dir(dir) = &node {
+                    if options.should_descend(&path, depth, dir) {
+                        push_children(&mut pending, &options, &path, depth, dir, store).await?;
+                    }
+                }
+
+                yield (path, node);
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Resolves the children of a directory and queues them to be visited in walk order.
+async fn push_children<B: BlockStore>(
+    pending: &mut VecDeque<(Vec<String>, usize, PublicNode)>,
+    options: &WalkOptions,
+    path: &[String],
+    depth: usize,
+    dir: &PublicDirectory,
+    store: &B,
+) -> Result<()> {
+    let mut children = Vec::with_capacity(dir.userland.len());
+    for (name, link) in dir.userland.iter() {
+        let mut child_path = path.to_vec();
+        child_path.push(name.clone());
+        children.push((
+            child_path,
+            depth + 1,
+            link.resolve_value(store).await?.clone(),
+        ));
+    }
+
+    match options.order {
+        WalkOrder::DepthFirst => {
+            for child in children.into_iter().rev() {
+                pending.push_front(child);
+            }
+        }
+        WalkOrder::BreadthFirst => pending.extend(children),
+    }
+
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod walk_tests {
+    use super::*;
+    use crate::{public::OpResult, MemoryBlockStore};
+    use chrono::Utc;
+    use futures::{pin_mut, StreamExt};
+    use libipld::Cid;
+
+    async fn collect_paths<B: BlockStore>(
+        root_dir: Rc<PublicDirectory>,
+        path_segments: &[String],
+        options: WalkOptions,
+        store: &B,
+    ) -> Vec<String> {
+        let nodes = root_dir.walk(path_segments, options, store);
+        pin_mut!(nodes);
+
+        let mut paths = vec![];
+        while let Some(item) = nodes.next().await {
+            paths.push(item.unwrap().0.join("/"));
+        }
+
+        paths
+    }
+
+    async fn sample_tree(store: &MemoryBlockStore) -> Rc<PublicDirectory> {
+        let time = Utc::now();
+        let mut root_dir = Rc::new(PublicDirectory::new(time));
+
+        for path in [
+            vec!["a", "b", "c.txt"],
+            vec!["a", "d.txt"],
+            vec!["e", "f.txt"],
+        ] {
+            let path_segments: Vec<String> = path.into_iter().map(String::from).collect();
+            let OpResult {
+                root_dir: new_root, ..
+            } = root_dir
+                .write(&path_segments, Cid::default(), time, store)
+                .await
+                .unwrap();
+
+            root_dir = new_root;
+        }
+
+        root_dir
+    }
+
+    #[async_std::test]
+    async fn walk_visits_nodes_in_requested_order() {
+        let store = MemoryBlockStore::default();
+        let root_dir = sample_tree(&store).await;
+
+        let depth_first = collect_paths(
+            Rc::clone(&root_dir),
+            &[],
+            WalkOrder::DepthFirst.into(),
+            &store,
+        )
+        .await;
+
+        assert_eq!(
+            depth_first,
+            ["a", "a/b", "a/b/c.txt", "a/d.txt", "e", "e/f.txt"]
+        );
+
+        let breadth_first =
+            collect_paths(root_dir, &[], WalkOrder::BreadthFirst.into(), &store).await;
+
+        assert_eq!(
+            breadth_first,
+            ["a", "e", "a/b", "a/d.txt", "e/f.txt", "a/b/c.txt"]
+        );
+    }
+
+    #[async_std::test]
+    async fn walk_respects_max_depth_and_prune() {
+        let store = MemoryBlockStore::default();
+        let root_dir = sample_tree(&store).await;
+
+        let options = WalkOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+
+        let paths = collect_paths(Rc::clone(&root_dir), &["a".into()], options, &store).await;
+
+        assert_eq!(paths, ["a/b", "a/d.txt"]);
+
+        let options = WalkOptions {
+            prune: Some(Box::new(|path, _| path.last().unwrap() == "a")),
+            ..Default::default()
+        };
+
+        let paths = collect_paths(root_dir, &[], options, &store).await;
+
+        assert_eq!(paths, ["a", "e", "e/f.txt"]);
+    }
+
+    #[async_std::test]
+    async fn walk_fails_on_missing_directory() {
+        let store = MemoryBlockStore::default();
+        let root_dir = sample_tree(&store).await;
+
+        let path_segments = ["missing".into()];
+        let nodes = root_dir.walk(&path_segments, WalkOrder::DepthFirst, &store);
+        pin_mut!(nodes);
+
+        assert!(nodes.next().await.unwrap().is_err());
+    }
+}
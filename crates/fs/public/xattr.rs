@@ -0,0 +1,258 @@
+//! Extended attributes of public nodes.
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use libipld::Ipld;
+
+use crate::BlockStore;
+
+use super::{OpResult, PublicDirectory};
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl PublicDirectory {
+    /// Sets an extended attribute on the node at the specified path.
+    ///
+    /// Symlinks are followed and an empty path refers to this directory. An attribute with the same name is
+    /// replaced. The modified time of the node is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, ipld::Ipld, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .mkdir(&["pictures".into()], Utc::now(), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { root_dir, .. } = root_dir
+    ///         .set_xattr(&["pictures".into()], "color", Ipld::String("blue".into()), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir
+    ///         .get_xattr(&["pictures".into()], "color", &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result, Some(Ipld::String("blue".into())));
+    /// }
+    /// ```
+    pub async fn set_xattr<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        name: &str,
+        value: Ipld,
+        store: &B,
+    ) -> Result<OpResult<()>> {
+        self.update_metadata(path_segments, store, |metadata| {
+            metadata.set_xattr(name, value)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Gets the value of an extended attribute of the node at the specified path.
+    pub async fn get_xattr<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        name: &str,
+        store: &B,
+    ) -> Result<OpResult<Option<Ipld>>> {
+        let root_dir = Rc::clone(&self);
+        let metadata = self.resolve_metadata(path_segments, store).await?;
+
+        Ok(OpResult {
+            root_dir,
+            result: metadata.get_xattr(name).cloned(),
+        })
+    }
+
+    /// Removes an extended attribute from the node at the specified path and returns its value.
+    ///
+    /// The tree is returned as is if the node doesn't have the attribute.
+    pub async fn remove_xattr<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        name: &str,
+        store: &B,
+    ) -> Result<OpResult<Option<Ipld>>> {
+        let root_dir = Rc::clone(&self);
+        let metadata = Rc::clone(&self)
+            .resolve_metadata(path_segments, store)
+            .await?;
+
+        if metadata.get_xattr(name).is_none() {
+            return Ok(OpResult {
+                root_dir,
+                result: None,
+            });
+        }
+
+        self.update_metadata(path_segments, store, |metadata| {
+            Ok(metadata.remove_xattr(name))
+        })
+        .await
+    }
+
+    /// Lists the names of the extended attributes of the node at the specified path in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{public::{PublicDirectory, OpResult}, ipld::Ipld, MemoryBlockStore};
+    /// use std::rc::Rc;
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = MemoryBlockStore::default();
+    ///
+    ///     let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(Utc::now()))
+    ///         .set_xattr(&[], "owner", Ipld::String("alice".into()), &store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let OpResult { result, .. } = root_dir.list_xattrs(&[], &store).await.unwrap();
+    ///
+    ///     assert_eq!(result, ["owner"]);
+    /// }
+    /// ```
+    pub async fn list_xattrs<B: BlockStore>(
+        self: Rc<Self>,
+        path_segments: &[String],
+        store: &B,
+    ) -> Result<OpResult<Vec<String>>> {
+        let root_dir = Rc::clone(&self);
+        let metadata = self.resolve_metadata(path_segments, store).await?;
+
+        Ok(OpResult {
+            root_dir,
+            result: metadata.list_xattrs().map(String::from).collect(),
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod xattr_tests {
+    use chrono::Utc;
+    use libipld::Cid;
+
+    use super::*;
+    use crate::{public::MoveMode, MemoryBlockStore};
+
+    #[async_std::test]
+    async fn xattrs_can_be_set_listed_and_removed() {
+        let time = Utc::now();
+        let store = MemoryBlockStore::default();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .write(&["cat.png".into()], Cid::default(), time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .set_xattr(
+                &["cat.png".into()],
+                "content-type",
+                Ipld::String("image/png".into()),
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .set_xattr(&["cat.png".into()], "rating", Ipld::Integer(5), &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, result } = root_dir
+            .list_xattrs(&["cat.png".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ["content-type", "rating"]);
+
+        let OpResult { root_dir, result } = root_dir
+            .remove_xattr(&["cat.png".into()], "rating", &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, Some(Ipld::Integer(5)));
+
+        let OpResult { root_dir, result } = root_dir
+            .remove_xattr(&["cat.png".into()], "rating", &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, None);
+
+        let OpResult { result, .. } = root_dir
+            .list_xattrs(&["cat.png".into()], &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ["content-type"]);
+    }
+
+    #[async_std::test]
+    async fn xattrs_survive_storing_and_moving() {
+        let time = Utc::now();
+        let mut store = MemoryBlockStore::default();
+
+        let OpResult { root_dir, .. } = Rc::new(PublicDirectory::new(time))
+            .mkdir(&["pictures".into(), "cats".into()], time, &store)
+            .await
+            .unwrap();
+
+        let OpResult { root_dir, .. } = root_dir
+            .set_xattr(
+                &["pictures".into(), "cats".into()],
+                "acl",
+                Ipld::List(vec![Ipld::String("alice".into())]),
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let cid = root_dir.store(&mut store).await.unwrap();
+        let root_dir = Rc::new(
+            store
+                .get_deserializable::<PublicDirectory>(&cid)
+                .await
+                .unwrap(),
+        );
+
+        let OpResult { root_dir, .. } = root_dir
+            .mv(
+                &["pictures".into(), "cats".into()],
+                &["cats".into()],
+                MoveMode::NoClobber,
+                time,
+                &store,
+            )
+            .await
+            .unwrap();
+
+        let OpResult { result, .. } = root_dir
+            .get_xattr(&["cats".into()], "acl", &store)
+            .await
+            .unwrap();
+
+        assert_eq!(result, Some(Ipld::List(vec![Ipld::String("alice".into())])));
+    }
+}
@@ -7,12 +7,12 @@ use js_sys::{Array, Date, Error, Promise, Uint8Array};
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use wasm_bindgen_futures::future_to_promise;
 use wnfs::{
-    ipld::Cid,
+    ipld::{Cid, Ipld},
     public::{
         OpResult as WnfsOpResult, PublicDirectory as WnfsPublicDirectory,
         PublicNode as WnfsPublicNode,
     },
-    Id,
+    Id, UnixFsMode,
 };
 
 use crate::fs::{BlockStore, ForeignBlockStore, JsResult, PublicNode};
@@ -168,6 +168,51 @@ impl PublicDirectory {
         }))
     }
 
+    /// Splits the bytes into blocks and writes a file pointing to them.
+    #[wasm_bindgen(js_name = "writeBytes")]
+    pub fn write_bytes(
+        &self,
+        path_segments: &Array,
+        content: Vec<u8>,
+        time: &Date,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let mut store = ForeignBlockStore(store);
+        let time = DateTime::<Utc>::from(time);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .write_bytes(&path_segments, &content, time, &mut store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot write to directory: {e}")))?;
+
+            let cid_u8array = Uint8Array::from(&result.to_bytes()[..]);
+
+            Ok(utils::create_op_result(root_dir, cid_u8array)?)
+        }))
+    }
+
+    /// Reads the content of a file written with `writeBytes` from the directory.
+    #[wasm_bindgen(js_name = "readBytes")]
+    pub fn read_bytes(&self, path_segments: &Array, store: BlockStore) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let mut store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .read_bytes(&path_segments, &mut store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot read from directory: {e}")))?;
+
+            let bytes_u8array = Uint8Array::from(&result[..]);
+
+            Ok(utils::create_op_result(root_dir, bytes_u8array)?)
+        }))
+    }
+
     /// Moves a specified path to a new location.
     pub fn basic_mv(
         &self,
@@ -192,6 +237,213 @@ impl PublicDirectory {
         }))
     }
 
+    /// Copies a node from one path to another, sharing its content with the source.
+    pub fn cp(
+        &self,
+        path_segments_from: &Array,
+        path_segments_to: &Array,
+        keep_history: bool,
+        time: &Date,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = self.0.clone();
+        let store = ForeignBlockStore(store);
+        let time = DateTime::<Utc>::from(time);
+        let path_segments_from = utils::convert_path_segments(path_segments_from)?;
+        let path_segments_to = utils::convert_path_segments(path_segments_to)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, .. } = directory
+                .cp(
+                    &path_segments_from,
+                    &path_segments_to,
+                    keep_history,
+                    time,
+                    &store,
+                )
+                .await
+                .map_err(|e| Error::new(&format!("Cannot copy node: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::NULL)?)
+        }))
+    }
+
+    /// Creates a symlink at the specified path that points to the target.
+    pub fn symlink(
+        &self,
+        target: String,
+        path_segments: &Array,
+        time: &Date,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let time = DateTime::<Utc>::from(time);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, .. } = directory
+                .symlink(&target, &path_segments, time, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot create symlink: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::NULL)?)
+        }))
+    }
+
+    /// Gets the target of the symlink at the specified path.
+    pub fn readlink(&self, path_segments: &Array, store: BlockStore) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .readlink(&path_segments, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot read symlink: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::from(result))?)
+        }))
+    }
+
+    /// Sets the permissions of the node at the specified path.
+    pub fn chmod(&self, path_segments: &Array, mode: u32, store: BlockStore) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+        let mode = UnixFsMode::new(mode).map_err(|e| Error::new(&format!("Invalid mode: {e}")))?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, .. } = directory
+                .chmod(&path_segments, mode, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot change mode: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::NULL)?)
+        }))
+    }
+
+    /// Sets the creation and modification times of the node at the specified path.
+    #[wasm_bindgen(js_name = "setTimes")]
+    pub fn set_times(
+        &self,
+        path_segments: &Array,
+        created: Option<Date>,
+        modified: Option<Date>,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+        let created = created.as_ref().map(DateTime::<Utc>::from);
+        let modified = modified.as_ref().map(DateTime::<Utc>::from);
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, .. } = directory
+                .set_times(&path_segments, created, modified, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot set times: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::NULL)?)
+        }))
+    }
+
+    /// Sets an extended attribute on the node at the specified path.
+    #[wasm_bindgen(js_name = "setXattr")]
+    pub fn set_xattr(
+        &self,
+        path_segments: &Array,
+        name: String,
+        value: JsValue,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+        let value = value
+            .into_serde::<Ipld>()
+            .map_err(|e| Error::new(&format!("Invalid attribute value: {e}")))?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, .. } = directory
+                .set_xattr(&path_segments, &name, value, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot set attribute: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, JsValue::NULL)?)
+        }))
+    }
+
+    /// Gets the value of an extended attribute of the node at the specified path.
+    #[wasm_bindgen(js_name = "getXattr")]
+    pub fn get_xattr(
+        &self,
+        path_segments: &Array,
+        name: String,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .get_xattr(&path_segments, &name, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot get attribute: {e}")))?;
+
+            let result = JsValue::from_serde(&result)
+                .map_err(|e| Error::new(&format!("Cannot convert attribute: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, result)?)
+        }))
+    }
+
+    /// Removes an extended attribute from the node at the specified path.
+    #[wasm_bindgen(js_name = "removeXattr")]
+    pub fn remove_xattr(
+        &self,
+        path_segments: &Array,
+        name: String,
+        store: BlockStore,
+    ) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .remove_xattr(&path_segments, &name, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot remove attribute: {e}")))?;
+
+            let result = JsValue::from_serde(&result)
+                .map_err(|e| Error::new(&format!("Cannot convert attribute: {e}")))?;
+
+            Ok(utils::create_op_result(root_dir, result)?)
+        }))
+    }
+
+    /// Lists the names of the extended attributes of the node at the specified path.
+    #[wasm_bindgen(js_name = "listXattrs")]
+    pub fn list_xattrs(&self, path_segments: &Array, store: BlockStore) -> JsResult<Promise> {
+        let directory = Rc::clone(&self.0);
+        let store = ForeignBlockStore(store);
+        let path_segments = utils::convert_path_segments(path_segments)?;
+
+        Ok(future_to_promise(async move {
+            let WnfsOpResult { root_dir, result } = directory
+                .list_xattrs(&path_segments, &store)
+                .await
+                .map_err(|e| Error::new(&format!("Cannot list attributes: {e}")))?;
+
+            let result = result.iter().map(|name| value!(name)).collect::<Array>();
+
+            Ok(utils::create_op_result(root_dir, result)?)
+        }))
+    }
+
     /// Creates a new directory at the specified path.
     ///
     /// This method acts like `mkdir -p` in Unix because it creates intermediate directories if they do not exist.
@@ -239,7 +491,7 @@ mod utils {
     use crate::{fs::JsResult, value};
     use js_sys::{Array, Error, Object, Reflect};
     use wasm_bindgen::JsValue;
-    use wnfs::public::PublicDirectory as WnfsPublicDirectory;
+    use wnfs::{public::PublicDirectory as WnfsPublicDirectory, WnfsPath};
 
     use super::PublicDirectory;
 
@@ -254,11 +506,13 @@ mod utils {
             .collect::<JsResult<Vec<_>>>()
     }
 
-    pub(crate) fn convert_path_segments(path_segments: &Array) -> JsResult<Vec<String>> {
-        map_to_rust_vec(path_segments, |v| {
+    pub(crate) fn convert_path_segments(path_segments: &Array) -> JsResult<WnfsPath> {
+        let path_segments = map_to_rust_vec(path_segments, |v| {
             v.as_string()
                 .ok_or_else(|| Error::new("Invalid path segments: Expected an array of strings"))
-        })
+        })?;
+
+        WnfsPath::new(path_segments).map_err(|e| Error::new(&format!("Invalid path segments: {e}")))
     }
 
     pub(crate) fn create_op_result<T: Into<JsValue>>(
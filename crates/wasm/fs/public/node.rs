@@ -25,6 +25,11 @@ impl PublicNode {
         self.0.is_dir()
     }
 
+    #[wasm_bindgen(js_name = "isSymlink")]
+    pub fn is_symlink(&self) -> bool {
+        self.0.is_symlink()
+    }
+
     #[wasm_bindgen(js_name = "getId")]
     pub fn get_id(&self) -> String {
         self.0.get_id()